@@ -0,0 +1,169 @@
+use serde::{Serialize, Deserialize};
+use sled::Db;
+
+// ── ANNOTATIONS ────────────────────────────────────────────────
+// Tags, rating, colour label and note per content hash, kept in version_db so
+// they survive clear_vault and follow a file through moves and renames.
+// "annotations" tree: hash → Annotation
+// "tags" tree:        {tag}::{hash} → ""   (lookup by tag, counts)
+
+pub const COLOR_LABELS: &[&str] = &["red", "orange", "yellow", "green", "blue", "purple", "gray"];
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Annotation {
+    pub tags: Vec<String>,
+    pub rating: Option<u8>,         // 1..=5
+    pub color: Option<String>,      // one of COLOR_LABELS
+    pub note: String,
+    pub updated_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+impl Annotation {
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.rating.is_none() && self.color.is_none() && self.note.is_empty()
+    }
+}
+
+fn annotations_tree(vdb: &Db) -> Result<sled::Tree, String> {
+    vdb.open_tree("annotations").map_err(|e| e.to_string())
+}
+
+fn tags_tree(vdb: &Db) -> Result<sled::Tree, String> {
+    vdb.open_tree("tags").map_err(|e| e.to_string())
+}
+
+fn tag_key(tag: &str, hash: &str) -> String {
+    format!("{}::{}", tag, hash)
+}
+
+// Tags are trimmed and lower-cased so "Work" and "work " are the same tag
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let t = tag.trim().to_lowercase();
+    (!t.is_empty() && !t.contains(':')).then_some(t)
+}
+
+pub fn get(vdb: &Db, hash: &str) -> Result<Annotation, String> {
+    let v = annotations_tree(vdb)?.get(hash.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(v.and_then(|v| serde_json::from_slice(&v).ok()).unwrap_or_default())
+}
+
+// Writes `annotation`, keeping the tag index in step with the previous tags
+pub fn put(vdb: &Db, hash: &str, mut annotation: Annotation) -> Result<Annotation, String> {
+    let previous = get(vdb, hash)?;
+    let tags = tags_tree(vdb)?;
+    for t in previous.tags.iter().filter(|t| !annotation.tags.contains(t)) {
+        tags.remove(tag_key(t, hash).as_bytes()).map_err(|e| e.to_string())?;
+    }
+    for t in &annotation.tags {
+        tags.insert(tag_key(t, hash).as_bytes(), &b""[..]).map_err(|e| e.to_string())?;
+    }
+    annotation.updated_at = crate::now_ts();
+    let tree = annotations_tree(vdb)?;
+    if annotation.is_empty() {
+        tree.remove(hash.as_bytes()).map_err(|e| e.to_string())?;
+    } else {
+        let encoded = serde_json::to_string(&annotation).map_err(|e| e.to_string())?;
+        tree.insert(hash.as_bytes(), encoded.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    Ok(annotation)
+}
+
+pub fn update<F: FnOnce(&mut Annotation)>(vdb: &Db, hash: &str, change: F) -> Result<Annotation, String> {
+    let mut annotation = get(vdb, hash)?;
+    change(&mut annotation);
+    put(vdb, hash, annotation)
+}
+
+// An edited file gets a new hash; its annotation moves along unless the new content already has one
+pub fn carry_forward(vdb: &Db, old_hash: &str, new_hash: &str) -> Result<(), String> {
+    let old = get(vdb, old_hash)?;
+    if old.is_empty() || !get(vdb, new_hash)?.is_empty() { return Ok(()); }
+    put(vdb, new_hash, old).map(|_| ())
+}
+
+pub fn tag_counts(vdb: &Db) -> Result<Vec<TagCount>, String> {
+    let mut counts: Vec<TagCount> = Vec::new();
+    for (k, _) in tags_tree(vdb)?.iter().filter_map(|r| r.ok()) {
+        let key = String::from_utf8_lossy(&k).to_string();
+        let Some((tag, _)) = key.split_once("::") else { continue };
+        match counts.last_mut() {
+            Some(last) if last.tag == tag => last.count += 1,
+            _ => counts.push(TagCount { tag: tag.to_string(), count: 1 }),
+        }
+    }
+    Ok(counts)
+}
+
+pub fn hashes_with_tag(vdb: &Db, tag: &str) -> Result<Vec<String>, String> {
+    Ok(tags_tree(vdb)?.scan_prefix(format!("{}::", tag).as_bytes())
+        .filter_map(|r| r.ok())
+        .map(|(k, _)| String::from_utf8_lossy(&k[tag.len() + 2..]).to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tagged(tags: &[&str]) -> Annotation {
+        Annotation { tags: tags.iter().map(|t| t.to_string()).collect(), ..Default::default() }
+    }
+
+    #[test]
+    fn tags_are_trimmed_lower_cased_and_checked() {
+        assert_eq!(normalize_tag("  Work "), Some("work".to_string()));
+        assert_eq!(normalize_tag("   "), None);
+        assert_eq!(normalize_tag("a:b"), None);
+    }
+
+    #[test]
+    fn put_drops_tags_that_are_no_longer_set() {
+        let vdb = sled::Config::new().temporary(true).open().unwrap();
+        put(&vdb, "h1", tagged(&["work", "taxes"])).unwrap();
+        put(&vdb, "h1", tagged(&["work", "2024"])).unwrap();
+        assert_eq!(hashes_with_tag(&vdb, "work").unwrap(), vec!["h1"]);
+        assert_eq!(hashes_with_tag(&vdb, "2024").unwrap(), vec!["h1"]);
+        assert!(hashes_with_tag(&vdb, "taxes").unwrap().is_empty());
+
+        // Clearing everything removes the record itself
+        put(&vdb, "h1", Annotation::default()).unwrap();
+        assert!(annotations_tree(&vdb).unwrap().is_empty());
+        assert!(tags_tree(&vdb).unwrap().is_empty());
+    }
+
+    #[test]
+    fn tag_counts_group_keys_by_tag() {
+        let vdb = sled::Config::new().temporary(true).open().unwrap();
+        put(&vdb, "h1", tagged(&["a", "ab"])).unwrap();
+        put(&vdb, "h2", tagged(&["a"])).unwrap();
+        put(&vdb, "h3", tagged(&["ab", "b"])).unwrap();
+        let counts: Vec<(String, usize)> = tag_counts(&vdb).unwrap().into_iter().map(|c| (c.tag, c.count)).collect();
+        assert_eq!(counts, vec![("a".to_string(), 2), ("ab".to_string(), 2), ("b".to_string(), 1)]);
+    }
+
+    #[test]
+    fn carry_forward_moves_to_unannotated_content_only() {
+        let vdb = sled::Config::new().temporary(true).open().unwrap();
+        put(&vdb, "old", Annotation { rating: Some(4), ..tagged(&["keep"]) }).unwrap();
+        carry_forward(&vdb, "old", "new").unwrap();
+        let moved = get(&vdb, "new").unwrap();
+        assert_eq!(moved.rating, Some(4));
+        assert_eq!(hashes_with_tag(&vdb, "keep").unwrap().len(), 2);
+
+        // Content that already has its own annotation keeps it
+        put(&vdb, "other", tagged(&["mine"])).unwrap();
+        carry_forward(&vdb, "old", "other").unwrap();
+        assert_eq!(get(&vdb, "other").unwrap().tags, vec!["mine"]);
+        assert_eq!(get(&vdb, "other").unwrap().rating, None);
+
+        // Nothing to carry from an unannotated hash
+        carry_forward(&vdb, "none", "fresh").unwrap();
+        assert!(get(&vdb, "fresh").unwrap().is_empty());
+    }
+}
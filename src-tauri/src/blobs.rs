@@ -0,0 +1,84 @@
+use blake3::Hasher;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// ── BLOB STORE ─────────────────────────────────────────────────
+// Content-addressed copies of file bytes: {blob_dir}/{hash[..2]}/{hash}
+// Identical files share one blob, so storing the same bytes twice is free.
+
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// Hashes arrive over IPC, so never let one be used as a path unless it is a BLAKE3 hex digest
+fn is_valid_hash(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn blob_path(blob_dir: &Path, hash: &str) -> PathBuf {
+    blob_dir.join(&hash[..2]).join(hash)
+}
+
+// Copies `src` into the store, hashing while copying so the blob is always
+// addressed by the bytes actually stored. Returns that hash.
+pub fn store(blob_dir: &Path, src: &Path) -> Result<String, String> {
+    let tmp_dir = blob_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).map_err(|e| e.to_string())?;
+    let tmp = tmp_dir.join(format!("{}-{}", std::process::id(), TMP_COUNTER.fetch_add(1, Ordering::Relaxed)));
+
+    let mut input = File::open(src).map_err(|e| e.to_string())?;
+    let mut output = File::create(&tmp).map_err(|e| e.to_string())?;
+    let mut hasher = Hasher::new();
+    let mut buffer = [0u8; 65536];
+    loop {
+        let n = input.read(&mut buffer).map_err(|e| e.to_string())?;
+        if n == 0 { break; }
+        hasher.update(&buffer[..n]);
+        output.write_all(&buffer[..n]).map_err(|e| e.to_string())?;
+    }
+    output.sync_all().map_err(|e| e.to_string())?;
+    drop(output);
+
+    let hash = hasher.finalize().to_string();
+    let dest = blob_path(blob_dir, &hash);
+    if dest.exists() {
+        fs::remove_file(&tmp).map_err(|e| e.to_string())?;
+    } else {
+        if let Some(p) = dest.parent() { fs::create_dir_all(p).map_err(|e| e.to_string())?; }
+        fs::rename(&tmp, &dest).map_err(|e| e.to_string())?;
+    }
+    Ok(hash)
+}
+
+pub fn restore(blob_dir: &Path, hash: &str, target: &Path) -> Result<(), String> {
+    if !is_valid_hash(hash) { return Err(format!("Invalid hash: {}", hash)); }
+    let src = blob_path(blob_dir, hash);
+    if !src.exists() { return Err(format!("No stored copy for {}", hash)); }
+    if let Some(p) = target.parent() { fs::create_dir_all(p).map_err(|e| e.to_string())?; }
+    fs::copy(&src, target).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn contains(blob_dir: &Path, hash: &str) -> bool {
+    is_valid_hash(hash) && blob_path(blob_dir, hash).is_file()
+}
+
+// Hash of every blob in the store; copies still being written live in tmp/ and are skipped
+pub fn list(blob_dir: &Path) -> Vec<String> {
+    let Ok(shards) = fs::read_dir(blob_dir) else { return Vec::new() };
+    shards.filter_map(|s| s.ok())
+        .filter(|s| s.file_name() != "tmp")
+        .filter_map(|s| fs::read_dir(s.path()).ok())
+        .flat_map(|files| files.filter_map(|f| f.ok()).map(|f| f.file_name().to_string_lossy().to_string()))
+        .filter(|h| is_valid_hash(h))
+        .collect()
+}
+
+// Returns whether a blob was actually there to remove
+pub fn remove(blob_dir: &Path, hash: &str) -> Result<bool, String> {
+    if !is_valid_hash(hash) { return Err(format!("Invalid hash: {}", hash)); }
+    let path = blob_path(blob_dir, hash);
+    if !path.exists() { return Ok(false); }
+    fs::remove_file(&path).map_err(|e| e.to_string())?;
+    Ok(true)
+}
@@ -0,0 +1,184 @@
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+
+use crate::filetype::{self, FileType};
+
+// ── CATEGORY RULES ─────────────────────────────────────────────
+// User rules live in settings::category_rules and are tried in order; the first
+// rule whose every given condition matches names the category. Files no rule
+// claims keep the category detected from their content.
+
+pub const BUILTIN: &[&str] = &["image", "video", "document", "audio", "archive", "executable", "code", "other"];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CategoryRule {
+    pub category: String,
+    #[serde(default)]
+    pub extensions: Vec<String>,       // without the dot, case-insensitive
+    #[serde(default)]
+    pub mime: Option<String>,          // exact, or a prefix ending in '*' ("image/*")
+    #[serde(default)]
+    pub path_glob: Option<String>,     // matched against the full path
+    #[serde(default)]
+    pub min_size: Option<u64>,
+    #[serde(default)]
+    pub max_size: Option<u64>,
+    #[serde(default)]
+    pub name_regex: Option<String>,    // matched against the file name
+}
+
+struct CompiledRule {
+    rule: CategoryRule,
+    glob: Option<GlobMatcher>,
+    name: Option<Regex>,
+}
+
+pub struct CategoryRules {
+    rules: Vec<CompiledRule>,
+}
+
+impl CategoryRule {
+    fn has_condition(&self) -> bool {
+        !self.extensions.is_empty() || self.mime.is_some() || self.path_glob.is_some()
+            || self.min_size.is_some() || self.max_size.is_some() || self.name_regex.is_some()
+    }
+}
+
+fn mime_matches(pattern: &str, mime: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => mime.starts_with(prefix),
+        None => pattern.eq_ignore_ascii_case(mime),
+    }
+}
+
+impl CategoryRules {
+    pub fn compile(rules: &[CategoryRule]) -> Result<Self, String> {
+        let mut compiled = Vec::with_capacity(rules.len());
+        for rule in rules {
+            if rule.category.trim().is_empty() { return Err("A rule needs a category name".to_string()); }
+            if rule.category.contains(':') { return Err(format!("Category names can't contain ':' ({})", rule.category)); }
+            if !rule.has_condition() { return Err(format!("Rule for '{}' has no conditions", rule.category)); }
+            let glob = match &rule.path_glob {
+                Some(g) => Some(Glob::new(g).map_err(|e| format!("Bad path glob '{}': {}", g, e))?.compile_matcher()),
+                None => None,
+            };
+            let name = match &rule.name_regex {
+                Some(r) => Some(Regex::new(r).map_err(|e| format!("Bad name regex '{}': {}", r, e))?),
+                None => None,
+            };
+            compiled.push(CompiledRule { rule: rule.clone(), glob, name });
+        }
+        Ok(CategoryRules { rules: compiled })
+    }
+
+    // Category for a file whose content was detected as `detected`
+    pub fn classify(&self, path: &str, size: u64, detected: &FileType) -> String {
+        let ext = filetype::extension_of(path);
+        let name = crate::file_name_of(path);
+        for c in &self.rules {
+            let r = &c.rule;
+            if !r.extensions.is_empty() && !r.extensions.iter().any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(&ext)) { continue; }
+            if let Some(m) = &r.mime { if !mime_matches(m, &detected.mime) { continue; } }
+            if let Some(g) = &c.glob { if !g.is_match(path) { continue; } }
+            if r.min_size.is_some_and(|min| size < min) || r.max_size.is_some_and(|max| size > max) { continue; }
+            if let Some(re) = &c.name { if !re.is_match(&name) { continue; } }
+            return r.category.clone();
+        }
+        detected.category.clone()
+    }
+
+    pub fn categories(&self) -> Vec<String> {
+        let mut names: Vec<String> = BUILTIN.iter().map(|c| c.to_string()).collect();
+        for c in &self.rules {
+            if !names.contains(&c.rule.category) { names.push(c.rule.category.clone()); }
+        }
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(category: &str) -> CategoryRule {
+        CategoryRule {
+            category: category.to_string(),
+            extensions: Vec::new(), mime: None, path_glob: None,
+            min_size: None, max_size: None, name_regex: None,
+        }
+    }
+
+    fn detected(mime: &str, category: &str) -> FileType {
+        FileType { mime: mime.to_string(), category: category.to_string() }
+    }
+
+    #[test]
+    fn compile_rejects_unusable_rules() {
+        let sized = |category: &str| CategoryRule { min_size: Some(1), ..rule(category) };
+        assert!(CategoryRules::compile(&[sized("  ")]).is_err());
+        assert!(CategoryRules::compile(&[sized("a:b")]).is_err());
+        assert!(CategoryRules::compile(&[rule("empty")]).is_err());
+        assert!(CategoryRules::compile(&[CategoryRule { path_glob: Some("[".to_string()), ..rule("glob") }]).is_err());
+        assert!(CategoryRules::compile(&[CategoryRule { name_regex: Some("(".to_string()), ..rule("regex") }]).is_err());
+        assert!(CategoryRules::compile(&[sized("fine")]).is_ok());
+    }
+
+    #[test]
+    fn each_condition_narrows_a_rule() {
+        let png = detected("image/png", "image");
+        let classify = |r: CategoryRule, path: &str, size: u64, t: &FileType| {
+            CategoryRules::compile(&[r]).unwrap().classify(path, size, t)
+        };
+
+        let by_ext = CategoryRule { extensions: vec![".PNG".to_string()], ..rule("pictures") };
+        assert_eq!(classify(by_ext.clone(), "/a/shot.png", 10, &png), "pictures");
+        assert_eq!(classify(by_ext, "/a/shot.jpg", 10, &png), "image");
+
+        let by_mime = CategoryRule { mime: Some("image/*".to_string()), ..rule("pictures") };
+        assert_eq!(classify(by_mime.clone(), "/a/shot", 10, &png), "pictures");
+        assert_eq!(classify(by_mime, "/a/clip", 10, &detected("video/mp4", "video")), "video");
+        let exact = CategoryRule { mime: Some("IMAGE/PNG".to_string()), ..rule("pictures") };
+        assert_eq!(classify(exact, "/a/shot", 10, &png), "pictures");
+
+        let by_glob = CategoryRule { path_glob: Some("/work/**".to_string()), ..rule("work") };
+        assert_eq!(classify(by_glob.clone(), "/work/q3/shot.png", 10, &png), "work");
+        assert_eq!(classify(by_glob, "/home/shot.png", 10, &png), "image");
+
+        let by_size = CategoryRule { min_size: Some(100), max_size: Some(200), ..rule("medium") };
+        assert_eq!(classify(by_size.clone(), "/a", 99, &png), "image");
+        assert_eq!(classify(by_size.clone(), "/a", 100, &png), "medium");
+        assert_eq!(classify(by_size.clone(), "/a", 200, &png), "medium");
+        assert_eq!(classify(by_size, "/a", 201, &png), "image");
+
+        // The regex sees only the file name, not the folders above it
+        let by_name = CategoryRule { name_regex: Some("^IMG_\\d+".to_string()), ..rule("camera") };
+        assert_eq!(classify(by_name.clone(), "/dcim/IMG_0042.png", 10, &png), "camera");
+        assert_eq!(classify(by_name, "/IMG_1/shot.png", 10, &png), "image");
+    }
+
+    #[test]
+    fn first_matching_rule_wins_and_all_conditions_must_hold() {
+        let rules = CategoryRules::compile(&[
+            CategoryRule { extensions: vec!["png".to_string()], min_size: Some(1000), ..rule("big-png") },
+            CategoryRule { mime: Some("image/*".to_string()), ..rule("pictures") },
+            CategoryRule { extensions: vec!["png".to_string()], ..rule("never") },
+        ]).unwrap();
+        let png = detected("image/png", "image");
+        assert_eq!(rules.classify("/a/x.png", 5000, &png), "big-png");
+        assert_eq!(rules.classify("/a/x.png", 10, &png), "pictures");
+        assert_eq!(rules.classify("/a/x.txt", 10, &detected("text/plain", "document")), "document");
+    }
+
+    #[test]
+    fn categories_add_rule_names_after_the_builtin_ones() {
+        let rules = CategoryRules::compile(&[
+            CategoryRule { min_size: Some(1), ..rule("work") },
+            CategoryRule { max_size: Some(1), ..rule("image") },
+            CategoryRule { max_size: Some(2), ..rule("work") },
+        ]).unwrap();
+        let names = rules.categories();
+        assert_eq!(&names[..BUILTIN.len()], BUILTIN);
+        assert_eq!(&names[BUILTIN.len()..], ["work"]);
+    }
+}
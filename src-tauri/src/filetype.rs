@@ -0,0 +1,171 @@
+use serde::{Serialize, Deserialize};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// ── FILE TYPE DETECTION ────────────────────────────────────────
+// Magic bytes decide when they are recognisable; the extension fills in for
+// formats without a signature (plain text, source code, svg, csv...).
+
+const SNIFF_BYTES: usize = 8192;
+
+// Zip-based formats that infer only reports as application/zip
+const ZIP_CONTAINERS: &[&str] = &["docx", "xlsx", "pptx", "odt", "ods", "odp", "epub", "jar", "apk", "ipa", "xpi", "cbz"];
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct FileType {
+    pub mime: String,       // "" when neither content nor extension says anything
+    pub category: String,
+}
+
+pub fn extension_of(path: &str) -> String {
+    Path::new(path).extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default()
+}
+
+pub fn extension_category(path: &str) -> &'static str {
+    match extension_of(path).as_str() {
+        "jpg"|"jpeg"|"png"|"gif"|"webp"|"bmp"|"svg"|"ico"|"heic"|"heif"|"avif"|"tif"|"tiff"|"raw"|"cr2"|"nef"|"dng" => "image",
+        "mp4"|"mkv"|"mov"|"avi"|"wmv"|"webm"|"flv"|"m4v"|"mpg"|"mpeg"|"3gp" => "video",
+        "pdf"|"doc"|"docx"|"txt"|"xlsx"|"xls"|"pptx"|"ppt"|"csv"|"md"|"rtf"|"odt"|"ods"|"odp"|"epub" => "document",
+        "mp3"|"wav"|"flac"|"aac"|"ogg"|"m4a"|"opus"|"wma"|"aiff" => "audio",
+        "zip"|"rar"|"7z"|"tar"|"gz"|"bz2"|"xz"|"zst"|"tgz" => "archive",
+        "exe"|"msi"|"dmg"|"deb"|"rpm"|"apk"|"appimage" => "executable",
+        "rs"|"py"|"js"|"jsx"|"ts"|"tsx"|"c"|"h"|"cpp"|"hpp"|"cs"|"java"|"kt"|"go"|"rb"|"php"|"swift"
+            |"sh"|"html"|"css"|"json"|"toml"|"yaml"|"yml"|"xml"|"sql" => "code",
+        _ => "other",
+    }
+}
+
+fn mime_category(mime: &str) -> Option<&'static str> {
+    let top = mime.split('/').next().unwrap_or("");
+    match (top, mime) {
+        ("image", _) => Some("image"),
+        ("video", _) => Some("video"),
+        ("audio", _) => Some("audio"),
+        (_, "application/pdf" | "application/msword" | "application/vnd.ms-excel" | "application/vnd.ms-powerpoint"
+            | "application/rtf" | "application/epub+zip" | "application/x-mobipocket-ebook") => Some("document"),
+        (_, m) if m.starts_with("application/vnd.openxmlformats-officedocument") || m.starts_with("application/vnd.oasis.opendocument") => Some("document"),
+        (_, "application/zip" | "application/vnd.rar" | "application/x-7z-compressed" | "application/x-tar" | "application/gzip"
+            | "application/x-bzip2" | "application/x-xz" | "application/zstd" | "application/x-lz4" | "application/x-compress"
+            | "application/x-cpio" | "application/vnd.ms-cab-compressed") => Some("archive"),
+        (_, "application/x-executable" | "application/vnd.microsoft.portable-executable" | "application/x-mach-binary"
+            | "application/vnd.debian.binary-package" | "application/x-rpm") => Some("executable"),
+        (_, "text/html" | "text/xml" | "text/x-shellscript") => Some("code"),
+        _ => None,
+    }
+}
+
+// No NUL bytes and valid UTF-8, allowing a character cut off by the sample end
+fn looks_like_text(sample: &[u8]) -> bool {
+    if sample.is_empty() || sample.contains(&0) { return false; }
+    match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+pub fn detect(path: &str) -> FileType {
+    let mut sample = Vec::with_capacity(SNIFF_BYTES);
+    if let Ok(f) = File::open(path) { let _ = f.take(SNIFF_BYTES as u64).read_to_end(&mut sample); }
+
+    if let Some(kind) = infer::get(&sample) { return from_mime(path, kind.mime_type()); }
+    if looks_like_text(&sample) { return from_mime(path, "text/plain"); }
+    from_mime(path, "")
+}
+
+// The detected type for a sniffed MIME ("" when nothing was recognised). Also rebuilds
+// it from FileMeta.mime for locations whose stat cache entry has no file type.
+pub fn from_mime(path: &str, mime: &str) -> FileType {
+    let by_ext = extension_category(path);
+    let category = match mime {
+        "" => by_ext,
+        "application/zip" if ZIP_CONTAINERS.contains(&extension_of(path).as_str()) => by_ext,
+        // Text is only what the extension claims if the extension is a text format. infer
+        // also calls anything opening with `<?xml` or an HTML tag xml/html, so an svg
+        // prolog or a README starting with a comment keeps its extension's category too.
+        m if m.starts_with("text/") => match by_ext {
+            "code" | "document" => by_ext,
+            "image" if extension_of(path) == "svg" => by_ext,
+            _ => mime_category(m).unwrap_or("document"),
+        },
+        m => mime_category(m).unwrap_or(by_ext),
+    };
+    FileType { mime: mime.to_string(), category: category.to_string() }
+}
+
+// The extension promises one kind of file and the bytes say another
+pub fn is_mismatch(path: &str, detected: &FileType) -> bool {
+    let by_ext = extension_category(path);
+    if by_ext == "other" || detected.mime.is_empty() { return false; }
+    by_ext != detected.category
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Writes `bytes` to a fresh file called `name` and detects it
+    fn detect_bytes(name: &str, bytes: &[u8]) -> FileType {
+        let dir = std::env::temp_dir().join(format!("filetype-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, bytes).unwrap();
+        let detected = detect(&path.to_string_lossy());
+        let _ = std::fs::remove_file(&path);
+        detected
+    }
+
+    #[test]
+    fn svg_with_an_xml_prolog_stays_an_image() {
+        let svg = detect_bytes("logo.svg", b"<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>");
+        assert_eq!(svg.mime, "text/xml");
+        assert_eq!(svg.category, "image");
+        assert!(!is_mismatch("logo.svg", &svg));
+        // An svg without a prolog is plain text to infer
+        assert_eq!(detect_bytes("icon.svg", b"<svg/>").category, "image");
+    }
+
+    #[test]
+    fn text_documents_opening_with_html_stay_documents() {
+        let readme = detect_bytes("README.md", b"<!-- generated -->\n# Title\n");
+        assert_eq!(readme.mime, "text/html");
+        assert_eq!(readme.category, "document");
+        assert!(!is_mismatch("README.md", &readme));
+        assert_eq!(from_mime("notes.txt", "text/html").category, "document");
+        assert_eq!(from_mime("page.html", "text/html").category, "code");
+        // A picture that is really markup is still flagged
+        assert_eq!(from_mime("photo.jpg", "text/xml").category, "code");
+        assert!(is_mismatch("photo.jpg", &from_mime("photo.jpg", "text/xml")));
+    }
+
+    #[test]
+    fn zip_containers_keep_their_extension() {
+        let zip = b"PK\x03\x04\x14\x00\x00\x00\x08\x00";
+        assert_eq!(from_mime("report.docx", "application/zip").category, "document");
+        assert_eq!(from_mime("app.apk", "application/zip").category, "executable");
+        assert_eq!(from_mime("bundle.zip", "application/zip").category, "archive");
+        let disguised = detect_bytes("holiday.jpg", zip);
+        assert_eq!(disguised.category, "archive");
+        assert!(is_mismatch("holiday.jpg", &disguised));
+    }
+
+    #[test]
+    fn extensionless_files_are_typed_by_content() {
+        assert_eq!(detect_bytes("report", b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n").category, "document");
+        assert_eq!(detect_bytes("notes", b"just some words\n").category, "document");
+        assert_eq!(detect_bytes("feed", b"<?xml version=\"1.0\"?><rss/>").category, "code");
+        assert_eq!(detect_bytes("blob", &[0, 1, 2, 3, 0xff]).category, "other");
+        // Nothing to compare the content against
+        assert!(!is_mismatch("report", &detect_bytes("report", b"%PDF-1.7\n")));
+    }
+
+    #[test]
+    fn misnamed_binaries_are_mismatches() {
+        let pdf = detect_bytes("scan.jpg", b"%PDF-1.4\n");
+        assert_eq!(pdf.mime, "application/pdf");
+        assert_eq!(pdf.category, "document");
+        assert!(is_mismatch("scan.jpg", &pdf));
+        // No signature and not text: the extension is all there is
+        assert!(!is_mismatch("data.csv", &from_mime("data.csv", "")));
+    }
+}
@@ -0,0 +1,547 @@
+use serde::{Serialize, Deserialize};
+use sled::Db;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::filetype::{self, FileType};
+use crate::FileMeta;
+
+// ── FULL-TEXT INDEX ────────────────────────────────────────────
+// Text pulled from document files, keyed by content hash so identical copies share
+// one entry and a moved file keeps its text. Trees in the vault db:
+//   "content_text":     hash → extracted text (capped)
+//   "content_len":      hash → token count, u32 LE; present once a hash is indexed
+//   "content_postings": {term}::{hash} → term frequency, u32 LE
+//   "content_stats":    "totals" → indexed hashes u64 LE, then their token count u64 LE,
+//                       kept in step with content_len so ranking never walks it
+
+const MAX_STORED_TEXT: usize = 1 << 20;
+const MAX_XML_BYTES: u64 = 32 << 20;
+const SNIPPET_BEFORE: usize = 80;
+const SNIPPET_AFTER: usize = 160;
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+const PDF_HELPER_ARG: &str = "--extract-pdf-text";
+const PDF_TIMEOUT: Duration = Duration::from_secs(60);
+const TOTALS_KEY: &[u8] = b"totals";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SnippetPart {
+    pub text: String,
+    pub hit: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ContentHit {
+    pub hash: String,
+    pub meta: FileMeta,
+    pub score: f32,
+    pub snippet: Vec<SnippetPart>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ContentPage {
+    pub total: usize,
+    pub offset: usize,
+    pub items: Vec<ContentHit>,
+}
+
+pub struct ContentIndex {
+    text: sled::Tree,
+    lengths: sled::Tree,
+    postings: sled::Tree,
+    stats: sled::Tree,
+}
+
+// ── EXTRACTION ─────────────────────────────────────────────────
+
+enum Source { Plain, Pdf, Docx, Xlsx, Pptx, OpenDocument }
+
+fn source_of(path: &str, detected: &FileType) -> Option<Source> {
+    if detected.category != "document" { return None; }
+    match filetype::extension_of(path).as_str() {
+        "pdf" => Some(Source::Pdf),
+        "docx" => Some(Source::Docx),
+        "xlsx" => Some(Source::Xlsx),
+        "pptx" => Some(Source::Pptx),
+        "odt" | "ods" | "odp" => Some(Source::OpenDocument),
+        _ if detected.mime.starts_with("text/") => Some(Source::Plain),
+        _ => None,
+    }
+}
+
+fn read_plain(path: &str) -> Result<String, String> {
+    let mut bytes = Vec::new();
+    File::open(path).map_err(|e| e.to_string())?
+        .take(MAX_STORED_TEXT as u64).read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// pdf-extract panics (and release builds abort on panic) or spins forever on some
+// malformed files, so PDFs are parsed by a copy of this executable started with
+// PDF_HELPER_ARG. It prints the text and exits 0, or prints the error and exits 1;
+// a crash or PDF_TIMEOUT costs that one file its text, never the scan.
+fn read_pdf(path: &str) -> Result<String, String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let mut child = Command::new(exe).arg(PDF_HELPER_ARG).arg(path)
+        .stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null())
+        .spawn().map_err(|e| e.to_string())?;
+    // Drained on its own thread so a large document can't fill the pipe and stall the helper
+    let mut stdout = child.stdout.take().ok_or("PDF helper has no stdout")?;
+    let reader = std::thread::spawn(move || {
+        let mut out = Vec::new();
+        let _ = stdout.read_to_end(&mut out);
+        out
+    });
+    let deadline = Instant::now() + PDF_TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? { break status; }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("PDF parser gave up after {}s", PDF_TIMEOUT.as_secs()));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    let out = String::from_utf8_lossy(&reader.join().unwrap_or_default()).into_owned();
+    match status.code() {
+        Some(0) => Ok(out),
+        Some(1) => Err(out),
+        _ => Err("PDF parser crashed".to_string()),
+    }
+}
+
+// Entry point of the PDF helper process; returns the exit code when this process is one
+pub fn run_pdf_helper() -> Option<i32> {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() != Some(PDF_HELPER_ARG) { return None; }
+    let path = args.next()?;
+    let (text, code) = match pdf_extract::extract_text(&path) {
+        Ok(mut text) => { truncate_text(&mut text); (text, 0) }
+        Err(e) => (e.to_string(), 1),
+    };
+    let mut stdout = std::io::stdout().lock();
+    if stdout.write_all(text.as_bytes()).and_then(|_| stdout.flush()).is_err() { return Some(2); }
+    Some(code)
+}
+
+fn truncate_text(text: &mut String) {
+    if text.len() > MAX_STORED_TEXT {
+        let mut cut = MAX_STORED_TEXT;
+        while !text.is_char_boundary(cut) { cut -= 1; }
+        text.truncate(cut);
+    }
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'), "lt" => Some('<'), "gt" => Some('>'), "quot" => Some('"'), "apos" => Some('\''),
+        _ => match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+            None => entity.strip_prefix('#')?.parse().ok().and_then(char::from_u32),
+        },
+    }
+}
+
+// Text content of an Office/OpenDocument XML part; paragraph ends become newlines
+fn xml_text(xml: &str) -> String {
+    let mut out = String::new();
+    let mut rest = xml;
+    while let Some(lt) = rest.find('<') {
+        push_decoded(&mut out, &rest[..lt]);
+        let Some(gt) = rest[lt..].find('>') else { break };
+        let tag = &rest[lt + 1..lt + gt];
+        let name = tag.trim_start_matches('/').split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or("");
+        let local = name.rsplit(':').next().unwrap_or(name);
+        match local {
+            "p" | "si" | "br" | "h" if tag.starts_with('/') || tag.ends_with('/') => out.push('\n'),
+            "tab" | "s" => out.push(' '),
+            _ => {}
+        }
+        rest = &rest[lt + gt + 1..];
+    }
+    push_decoded(&mut out, rest);
+    out
+}
+
+fn push_decoded(out: &mut String, text: &str) {
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        let after = &rest[amp + 1..];
+        match after.find(';').and_then(|semi| decode_entity(&after[..semi]).map(|c| (semi, c))) {
+            Some((semi, c)) => { out.push(c); rest = &after[semi + 1..]; }
+            None => { out.push('&'); rest = after; }
+        }
+    }
+    out.push_str(rest);
+}
+
+fn read_zip_xml(path: &str, parts: &dyn Fn(&str) -> bool) -> Result<String, String> {
+    let mut archive = zip::ZipArchive::new(File::open(path).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+    let mut names: Vec<String> = archive.file_names().filter(|n| parts(n)).map(String::from).collect();
+    // slide10 after slide9, not after slide1
+    names.sort_by_key(|n| (n.len(), n.clone()));
+    let mut out = String::new();
+    for name in names {
+        let mut xml = String::new();
+        archive.by_name(&name).map_err(|e| e.to_string())?
+            .take(MAX_XML_BYTES).read_to_string(&mut xml).map_err(|e| e.to_string())?;
+        out.push_str(&xml_text(&xml));
+        out.push('\n');
+        if out.len() >= MAX_STORED_TEXT { break; }
+    }
+    Ok(out)
+}
+
+// Searchable text of a document file, or None for files that carry none
+pub fn extract(path: &str, detected: &FileType) -> Option<Result<String, String>> {
+    let text = match source_of(path, detected)? {
+        Source::Plain => read_plain(path),
+        Source::Pdf => read_pdf(path),
+        Source::Docx => read_zip_xml(path, &|n| n == "word/document.xml"),
+        Source::Xlsx => read_zip_xml(path, &|n| n == "xl/sharedStrings.xml"),
+        Source::Pptx => read_zip_xml(path, &|n| n.starts_with("ppt/slides/slide") && n.ends_with(".xml")),
+        Source::OpenDocument => read_zip_xml(path, &|n| n == "content.xml"),
+    };
+    Some(text.map(|mut t| { truncate_text(&mut t); t }))
+}
+
+// ── TOKENS ─────────────────────────────────────────────────────
+
+// Byte spans of words: runs of alphanumerics, 2..=40 chars
+fn token_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start: Option<(usize, usize)> = None; // (byte offset, chars so far)
+    for (i, c) in text.char_indices() {
+        if c.is_alphanumeric() {
+            start = Some(match start { Some((s, n)) => (s, n + 1), None => (i, 1) });
+            continue;
+        }
+        if let Some((s, n)) = start.take() { if (2..=40).contains(&n) { spans.push((s, i)); } }
+    }
+    if let Some((s, n)) = start { if (2..=40).contains(&n) { spans.push((s, text.len())); } }
+    spans
+}
+
+fn tokens(text: &str) -> Vec<String> {
+    token_spans(text).into_iter().map(|(s, e)| text[s..e].to_lowercase()).collect()
+}
+
+fn posting_key(term: &str, hash: &str) -> String {
+    format!("{}::{}", term, hash)
+}
+
+fn read_u32(v: &[u8]) -> u32 {
+    v.try_into().map(u32::from_le_bytes).unwrap_or(0)
+}
+
+fn decode_totals(v: &[u8]) -> (u64, u64) {
+    let half = |r: std::ops::Range<usize>| v.get(r).and_then(|b| b.try_into().ok()).map(u64::from_le_bytes).unwrap_or(0);
+    (half(0..8), half(8..16))
+}
+
+fn encode_totals(docs: u64, tokens: u64) -> Vec<u8> {
+    [docs.to_le_bytes(), tokens.to_le_bytes()].concat()
+}
+
+// Lower-case with whitespace runs collapsed, for phrase matching
+fn flatten(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+// ── QUERY ──────────────────────────────────────────────────────
+// Words are ANDed; "quoted phrases" must also appear verbatim (case-insensitive)
+
+struct ContentQuery {
+    terms: Vec<String>,
+    phrases: Vec<String>,
+}
+
+fn parse_query(query: &str) -> ContentQuery {
+    let mut phrases = Vec::new();
+    for (i, part) in query.split('"').enumerate() {
+        if i % 2 == 1 && !part.trim().is_empty() { phrases.push(flatten(part)); }
+    }
+    let mut terms = tokens(query);
+    terms.sort();
+    terms.dedup();
+    ContentQuery { terms, phrases }
+}
+
+// Whitespace runs (newlines included) become single spaces
+fn squash(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if !c.is_whitespace() { out.push(c); } else if !out.ends_with(' ') { out.push(' '); }
+    }
+    out
+}
+
+fn snippet(text: &str, terms: &HashSet<&str>) -> Vec<SnippetPart> {
+    let spans: Vec<(usize, usize)> = token_spans(text).into_iter()
+        .filter(|&(s, e)| terms.contains(text[s..e].to_lowercase().as_str()))
+        .collect();
+    let (first_start, first_end) = spans.first().copied().unwrap_or((0, 0));
+    let mut start = first_start.saturating_sub(SNIPPET_BEFORE);
+    while !text.is_char_boundary(start) { start += 1; }
+    // Start on a word boundary rather than mid-word
+    if start > 0 { if let Some(ws) = text[start..first_start].find(char::is_whitespace) { start += ws + 1; } }
+    let mut end = (first_end + SNIPPET_AFTER).min(text.len());
+    while !text.is_char_boundary(end) { end += 1; }
+
+    let mut parts = Vec::new();
+    let mut push = |text: String, hit: bool| if !text.is_empty() { parts.push(SnippetPart { text, hit }) };
+    push(if start > 0 { "… ".to_string() } else { String::new() }, false);
+    let mut at = start;
+    for &(s, e) in spans.iter().filter(|&&(s, e)| s >= start && e <= end) {
+        push(squash(&text[at..s]), false);
+        push(text[s..e].to_string(), true);
+        at = e;
+    }
+    push(squash(&text[at..end]), false);
+    push(if end < text.len() { " …".to_string() } else { String::new() }, false);
+    parts
+}
+
+impl ContentIndex {
+    pub fn open(db: &Db) -> Result<Self, String> {
+        let open = |name: &str| db.open_tree(name).map_err(|e| e.to_string());
+        let index = ContentIndex { text: open("content_text")?, lengths: open("content_len")?, postings: open("content_postings")?, stats: open("content_stats")? };
+        // Indexes built before the running totals existed count them once
+        if !index.stats.contains_key(TOTALS_KEY).map_err(|e| e.to_string())? {
+            let (docs, tokens) = index.lengths.iter().values().filter_map(|v| v.ok())
+                .fold((0u64, 0u64), |(d, t), v| (d + 1, t + read_u32(&v) as u64));
+            let _ = index.stats.compare_and_swap(TOTALS_KEY, None as Option<&[u8]>, Some(encode_totals(docs, tokens)))
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(index)
+    }
+
+    // Moves the running totals by a hash's entry in content_len appearing, changing or going
+    fn adjust_totals(&self, previous: Option<sled::IVec>, current: Option<u32>) -> Result<(), String> {
+        let (docs, tokens) = match (previous.as_deref().map(read_u32), current) {
+            (None, None) => return Ok(()),
+            (None, Some(n)) => (1i64, n as i64),
+            (Some(old), None) => (-1, -(old as i64)),
+            (Some(old), Some(n)) => (0, n as i64 - old as i64),
+        };
+        self.stats.update_and_fetch(TOTALS_KEY, |v| {
+            let (d, t) = v.map(decode_totals).unwrap_or((0, 0));
+            Some(encode_totals(d.saturating_add_signed(docs), t.saturating_add_signed(tokens)))
+        }).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    // (indexed hashes, total tokens across them)
+    fn totals(&self) -> (u64, u64) {
+        self.stats.get(TOTALS_KEY).ok().flatten().map(|v| decode_totals(&v)).unwrap_or((0, 0))
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.lengths.contains_key(hash.as_bytes()).unwrap_or(false)
+    }
+
+    pub fn insert(&self, hash: &str, text: &str) -> Result<(), String> {
+        let words = tokens(text);
+        let mut tf: HashMap<&str, u32> = HashMap::new();
+        for w in &words { *tf.entry(w.as_str()).or_default() += 1; }
+        let mut batch = sled::Batch::default();
+        for (term, n) in tf { batch.insert(posting_key(term, hash).as_bytes(), &n.to_le_bytes()[..]); }
+        self.postings.apply_batch(batch).map_err(|e| e.to_string())?;
+        self.text.insert(hash.as_bytes(), text.as_bytes()).map_err(|e| e.to_string())?;
+        // Written last: a hash counts as indexed only once its postings are in
+        let previous = self.lengths.insert(hash.as_bytes(), &(words.len() as u32).to_le_bytes()[..]).map_err(|e| e.to_string())?;
+        self.adjust_totals(previous, Some(words.len() as u32))
+    }
+
+    // Extracts and indexes a file unless its content is already indexed. A file that
+    // fails to extract is recorded with no text so later scans don't retry it.
+    pub fn index_file(&self, hash: &str, path: &str, detected: &FileType) -> Result<(), String> {
+        if self.contains(hash) { return Ok(()); }
+        match extract(path, detected) {
+            None => Ok(()),
+            Some(Ok(text)) => self.insert(hash, &text),
+            Some(Err(e)) => { self.insert(hash, "")?; Err(format!("No text from {}: {}", path, e)) }
+        }
+    }
+
+    pub fn remove(&self, hash: &str) -> Result<(), String> {
+        let text = self.text.get(hash.as_bytes()).map_err(|e| e.to_string())?
+            .map(|v| String::from_utf8_lossy(&v).into_owned()).unwrap_or_default();
+        let previous = self.lengths.remove(hash.as_bytes()).map_err(|e| e.to_string())?;
+        self.adjust_totals(previous, None)?;
+        let mut batch = sled::Batch::default();
+        for term in tokens(&text).into_iter().collect::<HashSet<_>>() { batch.remove(posting_key(&term, hash).as_bytes()); }
+        self.postings.apply_batch(batch).map_err(|e| e.to_string())?;
+        self.text.remove(hash.as_bytes()).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    // Drops text for those of `candidates` no longer at any indexed location
+    pub fn prune<'a, I, F>(&self, candidates: I, is_live: F) -> Result<usize, String>
+    where
+        I: IntoIterator<Item = &'a str>,
+        F: Fn(&str) -> bool,
+    {
+        let mut dropped = 0;
+        for hash in candidates {
+            if is_live(hash) || !self.contains(hash) { continue; }
+            self.remove(hash)?;
+            dropped += 1;
+        }
+        Ok(dropped)
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        for tree in [&self.text, &self.lengths, &self.postings, &self.stats] { tree.clear().map_err(|e| e.to_string())?; }
+        Ok(())
+    }
+
+    fn text_of(&self, hash: &str) -> String {
+        self.text.get(hash.as_bytes()).ok().flatten().map(|v| String::from_utf8_lossy(&v).into_owned()).unwrap_or_default()
+    }
+
+    // BM25-ranked matches; `locate` maps a hash to one of its indexed locations and
+    // returns None for content no longer in the vault
+    pub fn search<F: Fn(&str) -> Option<FileMeta>>(&self, query: &str, offset: usize, limit: usize, locate: F) -> Result<ContentPage, String> {
+        let q = parse_query(query);
+        if q.terms.is_empty() { return Ok(ContentPage { total: 0, offset, items: Vec::new() }); }
+
+        let mut per_term: Vec<HashMap<String, u32>> = Vec::with_capacity(q.terms.len());
+        for term in &q.terms {
+            let prefix = format!("{}::", term);
+            per_term.push(self.postings.scan_prefix(prefix.as_bytes()).filter_map(|r| r.ok())
+                .map(|(k, v)| (String::from_utf8_lossy(&k[prefix.len()..]).into_owned(), read_u32(&v)))
+                .collect());
+        }
+        // Rarest term first keeps the intersection small
+        per_term.sort_by_key(|p| p.len());
+        let mut candidates: Vec<&String> = per_term[0].keys().filter(|h| per_term[1..].iter().all(|p| p.contains_key(*h))).collect();
+        if !q.phrases.is_empty() {
+            candidates.retain(|h| { let flat = flatten(&self.text_of(h)); q.phrases.iter().all(|p| flat.contains(p)) });
+        }
+
+        let (docs, total_len) = self.totals();
+        let docs = docs.max(1) as f32;
+        let avg_len = (total_len as f32 / docs).max(1.0);
+        let mut scored: Vec<(f32, &String, FileMeta)> = candidates.into_iter().filter_map(|hash| {
+            let meta = locate(hash)?;
+            let len = self.lengths.get(hash.as_bytes()).ok().flatten().map(|v| read_u32(&v)).unwrap_or(0) as f32;
+            let score = per_term.iter().map(|p| {
+                let df = p.len() as f32;
+                let tf = p[hash] as f32;
+                let idf = ((docs - df + 0.5) / (df + 0.5) + 1.0).ln();
+                idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * (1.0 - BM25_B + BM25_B * len / avg_len))
+            }).sum();
+            Some((score, hash, meta))
+        }).collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.2.path.cmp(&b.2.path)));
+
+        let total = scored.len();
+        let highlight: HashSet<&str> = q.terms.iter().map(String::as_str).collect();
+        let items = scored.into_iter().skip(offset).take(limit).map(|(score, hash, meta)| {
+            let snippet = snippet(&self.text_of(hash), &highlight);
+            ContentHit { hash: hash.clone(), meta, score, snippet }
+        }).collect();
+        Ok(ContentPage { total, offset: offset.min(total), items })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open() -> (Db, ContentIndex) {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let index = ContentIndex::open(&db).unwrap();
+        (db, index)
+    }
+
+    fn locate(hash: &str) -> Option<FileMeta> {
+        Some(FileMeta { path: format!("/docs/{}.txt", hash), size: 0, modified: String::new(), category: "document".to_string(), mime: "text/plain".to_string() })
+    }
+
+    fn ranked(index: &ContentIndex, query: &str) -> Vec<String> {
+        index.search(query, 0, 10, locate).unwrap().items.into_iter().map(|h| h.hash).collect()
+    }
+
+    #[test]
+    fn tokens_are_unicode_words_of_two_to_forty_chars() {
+        let long = "x".repeat(41);
+        let text = format!("Hi a, naïve CAFÉ—x 42 {} ok", long);
+        assert_eq!(tokens(&text), vec!["hi", "naïve", "café", "42", "ok"]);
+        let spans = token_spans("ünï ab");
+        assert_eq!(spans, vec![(0, 5), (6, 8)]);
+    }
+
+    #[test]
+    fn xml_text_decodes_entities_and_breaks_paragraphs() {
+        let xml = "<w:p><w:r><w:t>Tom &amp; Jerry&#39;s &#x263A; &lt;3</w:t></w:r></w:p><w:p><w:t>next</w:t><w:tab/>x</w:p>";
+        assert_eq!(xml_text(xml), "Tom & Jerry's \u{263A} <3\nnext x\n");
+        let mut out = String::new();
+        push_decoded(&mut out, "&nbsp; fish & chips &#xZZ; &quot;ok&quot;");
+        assert_eq!(out, "&nbsp; fish & chips &#xZZ; \"ok\"");
+    }
+
+    #[test]
+    fn queries_split_into_terms_and_phrases() {
+        let q = parse_query(r#"contract "Force   Majeure" clause contract"#);
+        assert_eq!(q.terms, vec!["clause", "contract", "force", "majeure"]);
+        assert_eq!(q.phrases, vec!["force majeure"]);
+        assert!(parse_query(r#"a "" b"#).phrases.is_empty());
+    }
+
+    #[test]
+    fn snippets_cut_multibyte_text_on_char_and_word_boundaries() {
+        let text = format!("{} {} needle {}", "é".repeat(30), "ü".repeat(30), "ß".repeat(200));
+        let parts = snippet(&text, &HashSet::from(["needle"]));
+        let texts: Vec<&str> = parts.iter().map(|p| p.text.as_str()).collect();
+        assert_eq!(texts[0], "… ");
+        assert_eq!(texts[1], format!("{} ", "ü".repeat(30)));
+        assert!(parts[2].hit && texts[2] == "needle");
+        assert_eq!(texts.last(), Some(&" …"));
+
+        let short = snippet("just one\n\n Needle here", &HashSet::from(["needle"]));
+        let texts: Vec<(&str, bool)> = short.iter().map(|p| (p.text.as_str(), p.hit)).collect();
+        assert_eq!(texts, vec![("just one ", false), ("Needle", true), (" here", false)]);
+    }
+
+    #[test]
+    fn bm25_prefers_frequent_terms_and_requires_every_word() {
+        let (_db, index) = open();
+        index.insert("short", "apple banana").unwrap();
+        index.insert("often", "apple apple apple banana cherry").unwrap();
+        index.insert("other", "cherry").unwrap();
+        assert_eq!(ranked(&index, "apple"), vec!["often", "short"]);
+        // Every word has to appear, and a quoted phrase verbatim
+        assert_eq!(ranked(&index, "apple cherry"), vec!["often"]);
+        assert_eq!(ranked(&index, "\"banana cherry\""), vec!["often"]);
+        assert_eq!(ranked(&index, "\"cherry banana\" apple"), Vec::<String>::new());
+        assert!(ranked(&index, "durian").is_empty());
+        // Content no longer at any location drops out of the results
+        assert_eq!(index.search("cherry", 0, 10, |h| if h == "other" { None } else { locate(h) }).unwrap().total, 1);
+    }
+
+    #[test]
+    fn running_totals_follow_inserts_and_removals() {
+        let (db, index) = open();
+        index.insert("a", "one two").unwrap();
+        index.insert("b", "three four five").unwrap();
+        index.insert("c", "six").unwrap();
+        assert_eq!(index.totals(), (3, 6));
+        index.insert("a", "one two seven eight").unwrap();
+        assert_eq!(index.totals(), (3, 8));
+        index.remove("c").unwrap();
+        index.remove("c").unwrap();
+        assert_eq!(index.totals(), (2, 7));
+        // An index from before the totals existed counts them on open
+        index.stats.clear().unwrap();
+        assert_eq!(ContentIndex::open(&db).unwrap().totals(), (2, 7));
+        index.clear().unwrap();
+        assert_eq!(index.totals(), (0, 0));
+    }
+}
@@ -0,0 +1,102 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use walkdir::{DirEntry, WalkDir};
+
+// ── IGNORE RULES ───────────────────────────────────────────────
+// gitignore syntax. A .vaultignore in any folder applies to everything below it and
+// the nearest one that mentions a path wins; the global list (settings::ignore) is
+// consulted last. Global patterns without a slash match at any depth. Both only look
+// inside the scan or watch root, so `target/` never matches a folder above it.
+
+pub const IGNORE_FILE: &str = ".vaultignore";
+
+pub fn default_patterns() -> Vec<String> {
+    [".git/", "node_modules/", "target/", "__pycache__/", ".cache/"].map(String::from).to_vec()
+}
+
+pub struct IgnoreRules {
+    global: Gitignore,
+    // Parsed .vaultignore per folder, None when the folder has none
+    per_dir: Mutex<HashMap<PathBuf, Option<Arc<Gitignore>>>>,
+}
+
+impl IgnoreRules {
+    pub fn new(patterns: &[String]) -> Result<Self, String> {
+        let mut builder = GitignoreBuilder::new("");
+        for p in patterns { builder.add_line(None, p).map_err(|e| e.to_string())?; }
+        let global = builder.build().map_err(|e| e.to_string())?;
+        Ok(IgnoreRules { global, per_dir: Mutex::new(HashMap::new()) })
+    }
+
+    fn dir_rules(&self, dir: &Path) -> Option<Arc<Gitignore>> {
+        let mut cache = self.per_dir.lock().ok()?;
+        cache.entry(dir.to_path_buf()).or_insert_with(|| {
+            let file = dir.join(IGNORE_FILE);
+            if !file.is_file() { return None; }
+            let (rules, _) = Gitignore::new(&file);
+            (!rules.is_empty()).then(|| Arc::new(rules))
+        }).clone()
+    }
+
+    // `root` is the folder being scanned or watched that contains `path`
+    pub fn is_ignored(&self, root: &Path, path: &Path, is_dir: bool) -> bool {
+        for dir in path.ancestors().skip(1) {
+            if let Some(rules) = self.dir_rules(dir) {
+                match rules.matched_path_or_any_parents(path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+            if dir == root { break; }
+        }
+        // The global matcher has no root of its own, so match on the path below the scan root.
+        // A path outside it falls back to the path minus its filesystem root.
+        let relative = match path.strip_prefix(root) {
+            Ok(rel) if !rel.has_root() => rel,
+            _ => path.strip_prefix(path.ancestors().last().unwrap_or(Path::new(""))).unwrap_or(path),
+        };
+        if relative.as_os_str().is_empty() { return false; }
+        self.global.matched_path_or_any_parents(relative, is_dir).is_ignore()
+    }
+}
+
+// WalkDir that never descends into ignored folders
+pub fn walk<'a>(root: &'a Path, rules: &'a IgnoreRules) -> impl Iterator<Item = DirEntry> + 'a {
+    walk_under(root, root, rules)
+}
+
+// The same, starting at `dir` somewhere inside `root`
+pub fn walk_under<'a>(root: &'a Path, dir: &Path, rules: &'a IgnoreRules) -> impl Iterator<Item = DirEntry> + 'a {
+    WalkDir::new(dir).into_iter()
+        .filter_entry(move |e| e.depth() == 0 || !rules.is_ignored(root, e.path(), e.file_type().is_dir()))
+        .filter_map(|e| e.ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> IgnoreRules {
+        IgnoreRules::new(&default_patterns()).unwrap()
+    }
+
+    #[test]
+    fn global_patterns_match_below_the_root() {
+        let root = Path::new("/home/me/project");
+        assert!(rules().is_ignored(root, Path::new("/home/me/project/target"), true));
+        assert!(rules().is_ignored(root, Path::new("/home/me/project/web/node_modules/x.js"), false));
+        assert!(!rules().is_ignored(root, Path::new("/home/me/project/src/main.rs"), false));
+    }
+
+    #[test]
+    fn global_patterns_ignore_ancestors_of_the_root() {
+        let root = Path::new("/home/me/.cache/target/photos");
+        assert!(!rules().is_ignored(root, Path::new("/home/me/.cache/target/photos/a.jpg"), false));
+        assert!(!rules().is_ignored(root, root, true));
+        assert!(rules().is_ignored(root, Path::new("/home/me/.cache/target/photos/.git/HEAD"), false));
+    }
+}
@@ -0,0 +1,302 @@
+use image::imageops::FilterType;
+use image::DynamicImage;
+use serde::{Serialize, Deserialize};
+
+// ── PERCEPTUAL HASHES ──────────────────────────────────────────
+// Four 64-bit hashes, pure Rust, each tolerant of different edits:
+//   phash  low DCT frequencies vs their mean: re-encodes, resizing, mild colour changes
+//   ahash  8x8 brightness vs the mean: cheap, but moves with global brightness edits
+//   dhash  brightness gradient between neighbours: exposure and colour grading
+//   whash  Haar wavelet low band vs its median (as Python's imagehash): crops, local edits
+// Each is taken for all eight rotations and mirrorings of the image, so a copy an
+// app turned or flipped still matches its original.
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Algorithm {
+    #[serde(rename = "phash")] Perceptual,
+    #[serde(rename = "ahash")] Average,
+    #[serde(rename = "dhash")] Difference,
+    #[serde(rename = "whash")] Wavelet,
+}
+
+impl Algorithm {
+    pub fn label(self) -> &'static str {
+        match self {
+            Algorithm::Perceptual => "pHash",
+            Algorithm::Average => "aHash",
+            Algorithm::Difference => "dHash",
+            Algorithm::Wavelet => "wHash",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Hashes {
+    pub phash: u64,
+    pub ahash: u64,
+    pub dhash: u64,
+    pub whash: u64,
+}
+
+impl Hashes {
+    pub fn get(&self, algorithm: Algorithm) -> u64 {
+        match algorithm {
+            Algorithm::Perceptual => self.phash,
+            Algorithm::Average => self.ahash,
+            Algorithm::Difference => self.dhash,
+            Algorithm::Wavelet => self.whash,
+        }
+    }
+}
+
+// The dihedral group, named after the EXIF orientations that produce the same pictures
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    Identity,
+    Rotate90,           // clockwise
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    Transpose,          // mirrored across the main diagonal
+    Transverse,         // mirrored across the anti-diagonal
+}
+
+impl Transform {
+    // Discriminant order, so `t as usize` indexes arrays laid out like this one
+    pub const ALL: [Transform; 8] = [
+        Transform::Identity, Transform::Rotate90, Transform::Rotate180, Transform::Rotate270,
+        Transform::FlipHorizontal, Transform::FlipVertical, Transform::Transpose, Transform::Transverse,
+    ];
+
+    pub fn inverse(self) -> Self {
+        match self {
+            Transform::Rotate90 => Transform::Rotate270,
+            Transform::Rotate270 => Transform::Rotate90,
+            t => t,
+        }
+    }
+
+    fn swaps_axes(self) -> bool {
+        matches!(self, Transform::Rotate90 | Transform::Rotate270 | Transform::Transpose | Transform::Transverse)
+    }
+
+    // A w x h row-major grid turned or mirrored; w and h trade places when the axes swap
+    fn apply(self, grid: &[f64], w: usize, h: usize) -> Vec<f64> {
+        let (ow, oh) = if self.swaps_axes() { (h, w) } else { (w, h) };
+        let mut out = Vec::with_capacity(grid.len());
+        for y in 0..oh {
+            for x in 0..ow {
+                let (sx, sy) = match self {
+                    Transform::Identity => (x, y),
+                    Transform::Rotate90 => (y, h - 1 - x),
+                    Transform::Rotate180 => (w - 1 - x, h - 1 - y),
+                    Transform::Rotate270 => (w - 1 - y, x),
+                    Transform::FlipHorizontal => (w - 1 - x, y),
+                    Transform::FlipVertical => (x, h - 1 - y),
+                    Transform::Transpose => (y, x),
+                    Transform::Transverse => (w - 1 - y, h - 1 - x),
+                };
+                out.push(grid[sy * w + sx]);
+            }
+        }
+        out
+    }
+}
+
+// Hashes of `img` after each transform, in Transform::ALL order. Turning the small
+// grids rather than the image gives the same pixels, since resizing commutes with it.
+pub fn compute(img: &DynamicImage) -> [Hashes; 8] {
+    // Every hash looks at a few thousand pixels at most; shrinking once up front keeps
+    // the Lanczos passes below cheap for camera-sized images
+    let small = if img.width() > 256 || img.height() > 256 { img.thumbnail(256, 256) } else { img.clone() };
+    let gray = small.grayscale();
+    let (g32, g8, g64) = (grid(&gray, 32, 32), grid(&gray, 8, 8), grid(&gray, 64, 64));
+    // dHash compares along rows, so it needs 9 columns after the transform
+    let (wide, tall) = (grid(&gray, 9, 8), grid(&gray, 8, 9));
+    Transform::ALL.map(|t| Hashes {
+        phash: phash(&t.apply(&g32, 32, 32)),
+        ahash: ahash(&t.apply(&g8, 8, 8)),
+        dhash: dhash(&if t.swaps_axes() { t.apply(&tall, 8, 9) } else { t.apply(&wide, 9, 8) }),
+        whash: whash(&t.apply(&g64, 64, 64)),
+    })
+}
+
+// Row-major brightness of `img` squeezed to w x h
+fn grid(img: &DynamicImage, w: u32, h: u32) -> Vec<f64> {
+    img.resize_exact(w, h, FilterType::Lanczos3).to_luma8().pixels().map(|p| p[0] as f64).collect()
+}
+
+// Bit i set when the i-th flag is true
+fn pack<I: IntoIterator<Item = bool>>(flags: I) -> u64 {
+    flags.into_iter().enumerate().fold(0, |hash, (i, set)| if set { hash | 1u64 << i } else { hash })
+}
+
+// 32x32 DCT; the top-left 8x8 coefficients minus DC, each compared with their mean
+fn phash(pixels: &[f64]) -> u64 {
+    let mut cos = [[0f64; 32]; 8];
+    for (u, row) in cos.iter_mut().enumerate() {
+        for (x, c) in row.iter_mut().enumerate() {
+            *c = ((2.0 * x as f64 + 1.0) * u as f64 * std::f64::consts::PI / 64.0).cos();
+        }
+    }
+    let scale = |k: usize| if k == 0 { 1.0 / 2f64.sqrt() } else { 1.0 };
+    let mut values = Vec::with_capacity(63);
+    for u in 0..8 {
+        for v in 0..8 {
+            if u == 0 && v == 0 { continue; }
+            let mut sum = 0f64;
+            for (y, row) in pixels.chunks(32).enumerate() {
+                for (x, p) in row.iter().enumerate() { sum += p * cos[u][x] * cos[v][y]; }
+            }
+            values.push((2.0 / 32.0) * scale(u) * scale(v) * sum);
+        }
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    pack(values.iter().map(|&v| v > mean))
+}
+
+fn ahash(pixels: &[f64]) -> u64 {
+    let mean = pixels.iter().sum::<f64>() / 64.0;
+    pack(pixels.iter().map(|&p| p > mean))
+}
+
+// 9x8 so each row yields 8 left-to-right comparisons
+fn dhash(pixels: &[f64]) -> u64 {
+    pack(pixels.chunks(9).flat_map(|row| row.windows(2).map(|w| w[0] < w[1]).collect::<Vec<_>>()))
+}
+
+// One 2D Haar step over the top-left n x n block of a 64-wide grid, leaving the
+// block's low band in its top-left quarter
+fn haar_step(g: &mut [f64], n: usize) {
+    let half = n / 2;
+    let mut rows = vec![0f64; n * n];
+    for y in 0..n {
+        for x in 0..half {
+            let (a, b) = (g[y * 64 + 2 * x], g[y * 64 + 2 * x + 1]);
+            rows[y * n + x] = (a + b) / std::f64::consts::SQRT_2;
+            rows[y * n + half + x] = (a - b) / std::f64::consts::SQRT_2;
+        }
+    }
+    for x in 0..n {
+        for y in 0..half {
+            let (a, b) = (rows[2 * y * n + x], rows[(2 * y + 1) * n + x]);
+            g[y * 64 + x] = (a + b) / std::f64::consts::SQRT_2;
+            g[(half + y) * 64 + x] = (a - b) / std::f64::consts::SQRT_2;
+        }
+    }
+}
+
+// 64x64 with the overall Haar LL (the mean) removed, then three Haar steps down to an 8x8 low band
+fn whash(grid: &[f64]) -> u64 {
+    let mut pixels: Vec<f64> = grid.iter().map(|p| p / 255.0).collect();
+    let mean = pixels.iter().sum::<f64>() / pixels.len() as f64;
+    for p in pixels.iter_mut() { *p -= mean; }
+    for n in [64, 32, 16] { haar_step(&mut pixels, n); }
+    let low: Vec<f64> = (0..8).flat_map(|y| pixels[y * 64..y * 64 + 8].to_vec()).collect();
+    let mut sorted = low.clone();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = (sorted[31] + sorted[32]) / 2.0;
+    pack(low.iter().map(|&v| v > median))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    const LEFT: u64 = 0x0f0f_0f0f_0f0f_0f0f;     // left half of every row
+    const RIGHT: u64 = 0xf0f0_f0f0_f0f0_f0f0;
+    const TOP: u64 = 0x0000_0000_ffff_ffff;      // first four rows
+    const BOTTOM: u64 = 0xffff_ffff_0000_0000;
+    const PHASH_BITS: u64 = (1 << 63) - 1;   // 63 AC coefficients
+
+    // w x h grid with the left half bright
+    fn left_bright(w: usize, h: usize) -> Vec<f64> {
+        (0..w * h).map(|i| if i % w < w / 2 { 255.0 } else { 0.0 }).collect()
+    }
+
+    #[test]
+    fn ahash_known_answers() {
+        assert_eq!(ahash(&left_bright(8, 8)), LEFT);
+        let stripes: Vec<f64> = (0..64).map(|i| if (i / 8) % 2 == 0 { 200.0 } else { 10.0 }).collect();
+        assert_eq!(ahash(&stripes), 0x00ff_00ff_00ff_00ff);
+    }
+
+    #[test]
+    fn dhash_known_answers() {
+        let rising: Vec<f64> = (0..72).map(|i| (i % 9) as f64).collect();
+        assert_eq!(dhash(&rising), u64::MAX);
+        let falling: Vec<f64> = rising.iter().map(|p| 8.0 - p).collect();
+        assert_eq!(dhash(&falling), 0);
+        // Only the step between columns 3 and 4 rises
+        let step: Vec<f64> = (0..72).map(|i| if i % 9 >= 4 { 255.0 } else { 0.0 }).collect();
+        assert_eq!(dhash(&step), 0x0808_0808_0808_0808);
+    }
+
+    #[test]
+    fn phash_known_answers() {
+        // A left/right split only has odd horizontal frequencies (u, 0), at bit 8u - 1.
+        // u = 1 and 5 come out positive and above the mean; everything else is below it.
+        let odd = 1 << 7 | 1 << 39;
+        assert_eq!(phash(&left_bright(32, 32)), odd);
+        // Mirroring flips their signs, so now only those two fall below the mean
+        assert_eq!(phash(&Transform::FlipHorizontal.apply(&left_bright(32, 32), 32, 32)), PHASH_BITS & !odd);
+    }
+
+    #[test]
+    fn whash_known_answers() {
+        assert_eq!(whash(&left_bright(64, 64)), LEFT);
+        assert_eq!(whash(&Transform::Rotate90.apply(&left_bright(64, 64), 64, 64)), TOP);
+    }
+
+    #[test]
+    fn transforms_move_pixels_as_named() {
+        // 0 1 2
+        // 3 4 5
+        let grid: Vec<f64> = (0..6).map(|v| v as f64).collect();
+        let expected: [(Transform, [u8; 6]); 8] = [
+            (Transform::Identity, [0, 1, 2, 3, 4, 5]),
+            (Transform::Rotate90, [3, 0, 4, 1, 5, 2]),
+            (Transform::Rotate180, [5, 4, 3, 2, 1, 0]),
+            (Transform::Rotate270, [2, 5, 1, 4, 0, 3]),
+            (Transform::FlipHorizontal, [2, 1, 0, 5, 4, 3]),
+            (Transform::FlipVertical, [3, 4, 5, 0, 1, 2]),
+            (Transform::Transpose, [0, 3, 1, 4, 2, 5]),
+            (Transform::Transverse, [5, 2, 4, 1, 3, 0]),
+        ];
+        for (t, pixels) in expected {
+            let want: Vec<f64> = pixels.iter().map(|&p| p as f64).collect();
+            assert_eq!(t.apply(&grid, 3, 2), want, "{:?}", t);
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_each_transform() {
+        let grid: Vec<f64> = (0..6).map(|v| v as f64).collect();
+        for t in Transform::ALL {
+            let (w, h) = if t.swaps_axes() { (2, 3) } else { (3, 2) };
+            assert_eq!(t.inverse().apply(&t.apply(&grid, 3, 2), w, h), grid, "{:?}", t);
+        }
+    }
+
+    #[test]
+    fn compute_hashes_each_orientation() {
+        let img = DynamicImage::ImageLuma8(GrayImage::from_fn(64, 64, |x, _| Luma([if x < 32 { 255 } else { 0 }])));
+        let hashes = compute(&img);
+        // Where the bright half ends up after each transform
+        let bright = [LEFT, TOP, RIGHT, BOTTOM, RIGHT, LEFT, TOP, BOTTOM];
+        for (t, want) in Transform::ALL.iter().zip(bright) {
+            let h = hashes[*t as usize];
+            assert_eq!(h.ahash, want, "aHash {:?}", t);
+            assert_eq!(h.whash, want, "wHash {:?}", t);
+        }
+        // Turning the image itself gives the hashes compute derived from the upright one
+        let turned = compute(&img.rotate90())[Transform::Identity as usize];
+        let derived = hashes[Transform::Rotate90 as usize];
+        for algorithm in [Algorithm::Perceptual, Algorithm::Average, Algorithm::Difference, Algorithm::Wavelet] {
+            assert_eq!(turned.get(algorithm), derived.get(algorithm), "{}", algorithm.label());
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use serde::{Serialize, Deserialize};
+use sled::transaction::{ConflictableTransactionResult, TransactionError, TransactionalTree, Transactional};
+use sled::Db;
+
+use crate::FileMeta;
+
+// ── SECONDARY INDEXES ──────────────────────────────────────────
+// Derived from the primary "{hash}::{path}" tree and written in the same sled
+// transaction (put_locations / remove_location), so folder and category lookups
+// touch only the entries they return.
+//   "by_path":      {normalised path}\0{path} → hash
+//   "by_category":  {category}::{path} → hash
+//   "dir_children": {normalised dir}/\0{normalised name} → DirChild
+//   "sort_keys":    {field}::{sort key}\0{path} → hash, for name/size/modified order
+
+const INDEX_VERSION: &[u8] = b"2";
+const SORT_FIELDS: &[&str] = &["name", "size", "modified"];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DirChild {
+    pub name: String,
+    pub path: String,
+    pub is_folder: bool,
+    pub files: u64,     // indexed files at or below this entry
+    pub size: u64,
+}
+
+pub struct Indexes {
+    pub by_path: sled::Tree,
+    pub by_category: sled::Tree,
+    pub dirs: sled::Tree,
+    pub sorted: sled::Tree,
+}
+
+// The index trees as seen from inside a transaction
+pub struct IndexTx<'a> {
+    pub by_path: &'a TransactionalTree,
+    pub by_category: &'a TransactionalTree,
+    pub dirs: &'a TransactionalTree,
+    pub sorted: &'a TransactionalTree,
+}
+
+// One page of (hash, path) in index order and the cursor for the page after it
+pub struct KeyPage {
+    pub entries: Vec<(String, String)>,
+    pub next: Option<String>,
+}
+
+pub fn normalize(path: &str) -> String {
+    path.replace('\\', "/").to_lowercase()
+}
+
+// "C:\Photos" and "/home/u/" → "c:/photos/", "/home/u/": everything strictly inside the folder
+pub fn folder_prefix(folder: &str) -> String {
+    format!("{}/", normalize(folder).trim_end_matches('/'))
+}
+
+fn path_key(path: &str) -> String {
+    format!("{}\0{}", normalize(path), path)
+}
+
+fn category_key(category: &str, path: &str) -> String {
+    format!("{}::{}", category, path)
+}
+
+// Fixed-width numbers so byte order is numeric order
+fn sort_key(field: &str, meta: &FileMeta) -> String {
+    let value = match field {
+        "name" => crate::file_name_of(&meta.path).to_lowercase(),
+        "size" => format!("{:020}", meta.size),
+        _ => format!("{:020}", crate::search::modified_secs(&meta.modified).unwrap_or(0)),
+    };
+    format!("{}::{}\0{}", field, value, meta.path)
+}
+
+// (dir_children key, name, path, is_folder) for every folder level of `path`, and the file itself
+fn ancestry(path: &str) -> Vec<(String, &str, &str, bool)> {
+    let slashes: Vec<usize> = path.match_indices(['/', '\\']).map(|(i, _)| i).collect();
+    let mut out = Vec::with_capacity(slashes.len());
+    for (n, &slash) in slashes.iter().enumerate() {
+        let end = slashes.get(n + 1).copied().unwrap_or(path.len());
+        let name = &path[slash + 1..end];
+        if name.is_empty() { continue; }
+        let key = format!("{}\0{}", normalize(&path[..=slash]), name.to_lowercase());
+        out.push((key, name, &path[..end], end < path.len()));
+    }
+    out
+}
+
+fn adjust_dirs(dirs: &TransactionalTree, meta: &FileMeta, delta: i64) -> ConflictableTransactionResult<(), String> {
+    for (key, name, child_path, is_folder) in ancestry(&meta.path) {
+        let current: Option<DirChild> = dirs.get(key.as_bytes())?.and_then(|v| serde_json::from_slice(&v).ok());
+        let mut child = current.unwrap_or(DirChild { name: name.to_string(), path: child_path.to_string(), is_folder, files: 0, size: 0 });
+        child.files = child.files.saturating_add_signed(delta);
+        child.size = if delta > 0 { child.size.saturating_add(meta.size) } else { child.size.saturating_sub(meta.size) };
+        if child.files == 0 {
+            dirs.remove(key.as_bytes())?;
+        } else {
+            let encoded = serde_json::to_vec(&child).map_err(|e| sled::transaction::ConflictableTransactionError::Abort(e.to_string()))?;
+            dirs.insert(key.as_bytes(), encoded)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn add(tx: &IndexTx, hash: &str, meta: &FileMeta) -> ConflictableTransactionResult<(), String> {
+    tx.by_path.insert(path_key(&meta.path).as_bytes(), hash.as_bytes())?;
+    tx.by_category.insert(category_key(&meta.category, &meta.path).as_bytes(), hash.as_bytes())?;
+    for field in SORT_FIELDS { tx.sorted.insert(sort_key(field, meta).as_bytes(), hash.as_bytes())?; }
+    adjust_dirs(tx.dirs, meta, 1)
+}
+
+pub fn remove(tx: &IndexTx, meta: &FileMeta) -> ConflictableTransactionResult<(), String> {
+    tx.by_path.remove(path_key(&meta.path).as_bytes())?;
+    tx.by_category.remove(category_key(&meta.category, &meta.path).as_bytes())?;
+    for field in SORT_FIELDS { tx.sorted.remove(sort_key(field, meta).as_bytes())?; }
+    adjust_dirs(tx.dirs, meta, -1)
+}
+
+// (hash, path) for keys under `prefix`; `path_of` picks the path out of the rest of the key
+fn scan_pairs(tree: &sled::Tree, prefix: &str, path_of: fn(&str) -> &str) -> Vec<(String, String)> {
+    tree.scan_prefix(prefix.as_bytes())
+        .filter_map(|r| r.ok())
+        .map(|(k, v)| {
+            let key = String::from_utf8_lossy(&k).to_string();
+            (String::from_utf8_lossy(&v).to_string(), path_of(&key[prefix.len()..]).to_string())
+        })
+        .collect()
+}
+
+impl Indexes {
+    pub fn open(db: &Db) -> Result<Self, String> {
+        let open = |name: &str| db.open_tree(name).map_err(|e| e.to_string());
+        Ok(Indexes { by_path: open("by_path")?, by_category: open("by_category")?, dirs: open("dir_children")?, sorted: open("sort_keys")? })
+    }
+
+    // Every (hash, path) strictly inside `folder`
+    pub fn under(&self, folder: &str) -> Vec<(String, String)> {
+        self.with_prefix(&folder_prefix(folder))
+    }
+
+    // Every (hash, path) whose normalised path starts with `prefix`
+    pub fn with_prefix(&self, prefix: &str) -> Vec<(String, String)> {
+        scan_pairs(&self.by_path, prefix, |rest| rest.split_once('\0').map_or(rest, |(_, path)| path))
+    }
+
+    pub fn in_category(&self, category: &str) -> Vec<(String, String)> {
+        scan_pairs(&self.by_category, &format!("{}::", category), |rest| rest)
+    }
+
+    pub fn children(&self, folder: &str) -> Vec<DirChild> {
+        self.dirs.scan_prefix(format!("{}\0", folder_prefix(folder)).as_bytes())
+            .filter_map(|r| r.ok())
+            .filter_map(|(_, v)| serde_json::from_slice(&v).ok())
+            .collect()
+    }
+
+    // Up to `limit` locations ordered by `sort_by` ("path", "name", "size", "modified" or
+    // "hash"), starting after `after`, the opaque cursor handed out with the previous page
+    pub fn page(&self, db: &Db, sort_by: &str, after: Option<&str>, limit: usize, descending: bool) -> Result<KeyPage, String> {
+        let field_prefix;
+        let (tree, prefix): (&sled::Tree, &str) = match sort_by {
+            "hash" => (&**db, ""),
+            "name" | "size" | "modified" => { field_prefix = format!("{}::", sort_by); (&self.sorted, &field_prefix) }
+            _ => (&self.by_path, ""),
+        };
+        let start = prefix.as_bytes().to_vec();
+        let cursor = after.map(|a| format!("{}{}", prefix, a).into_bytes());
+        let iter: Box<dyn Iterator<Item = sled::Result<(sled::IVec, sled::IVec)>>> = match (&cursor, descending) {
+            (Some(c), false) => Box::new(tree.range(c.clone()..).skip_while(move |r| matches!(r, Ok((k, _)) if k == c))),
+            (Some(c), true) => Box::new(tree.range(start..c.clone()).rev()),
+            (None, false) => Box::new(tree.scan_prefix(&start)),
+            (None, true) => Box::new(tree.scan_prefix(&start).rev()),
+        };
+        let mut entries = Vec::with_capacity(limit);
+        let mut last = None;
+        for (k, v) in iter.filter_map(|r| r.ok()) {
+            if !k.starts_with(prefix.as_bytes()) { break; }
+            if entries.len() == limit { return Ok(KeyPage { entries, next: last }); }
+            let rest = String::from_utf8_lossy(&k[prefix.len()..]).to_string();
+            let entry = if sort_by == "hash" {
+                let Some((hash, path)) = rest.split_once("::") else { continue };
+                (hash.to_string(), path.to_string())
+            } else {
+                (String::from_utf8_lossy(&v).to_string(), rest.split_once('\0').map_or(rest.as_str(), |(_, p)| p).to_string())
+            };
+            entries.push(entry);
+            last = Some(rest);
+        }
+        Ok(KeyPage { entries, next: None })
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        for tree in [&self.by_path, &self.by_category, &self.dirs, &self.sorted] { tree.clear().map_err(|e| e.to_string())?; }
+        Ok(())
+    }
+
+    // Vaults written before these trees existed get them built once at startup
+    pub fn ensure_built(&self, db: &Db) -> Result<(), String> {
+        let meta = db.open_tree("index_meta").map_err(|e| e.to_string())?;
+        if meta.get(b"version").map_err(|e| e.to_string())?.as_deref() == Some(INDEX_VERSION) { return Ok(()); }
+        self.clear()?;
+        let entries: Vec<(String, FileMeta)> = db.iter().filter_map(|r| r.ok()).filter_map(|(k, v)| {
+            let key = String::from_utf8_lossy(&k).to_string();
+            Some((key.split("::").next()?.to_string(), serde_json::from_slice(&v).ok()?))
+        }).collect();
+        for chunk in entries.chunks(1024) {
+            (&self.by_path, &self.by_category, &self.dirs, &self.sorted).transaction(|(by_path, by_category, dirs, sorted)| {
+                let tx = IndexTx { by_path, by_category, dirs, sorted };
+                for (hash, meta) in chunk { add(&tx, hash, meta)?; }
+                Ok(())
+            }).map_err(|e: TransactionError<String>| e.to_string())?;
+        }
+        meta.insert(b"version", INDEX_VERSION).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(path: &str, size: u64) -> FileMeta {
+        FileMeta { path: path.to_string(), size, modified: String::new(), category: "document".to_string(), mime: String::new() }
+    }
+
+    fn open() -> (Db, Indexes) {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let idx = Indexes::open(&db).unwrap();
+        (db, idx)
+    }
+
+    // Adds (or with `added` false, removes) each location's index entries in one transaction
+    fn apply(idx: &Indexes, files: &[FileMeta], added: bool) {
+        (&idx.by_path, &idx.by_category, &idx.dirs, &idx.sorted).transaction(|(by_path, by_category, dirs, sorted)| {
+            let tx = IndexTx { by_path, by_category, dirs, sorted };
+            for m in files {
+                if added { add(&tx, &format!("h{}", m.size), m)?; } else { remove(&tx, m)?; }
+            }
+            Ok(())
+        }).map_err(|e: TransactionError<String>| e.to_string()).unwrap();
+    }
+
+    fn child<'a>(children: &'a [DirChild], name: &str) -> &'a DirChild {
+        children.iter().find(|c| c.name == name).unwrap()
+    }
+
+    fn paths(pairs: &[(String, String)]) -> Vec<&str> {
+        let mut out: Vec<&str> = pairs.iter().map(|(_, p)| p.as_str()).collect();
+        out.sort_unstable();
+        out
+    }
+
+    #[test]
+    fn folder_prefix_normalises_separators_and_trailing_slashes() {
+        assert_eq!(folder_prefix("C:\\Photos"), "c:/photos/");
+        assert_eq!(folder_prefix("C:\\Photos\\"), "c:/photos/");
+        assert_eq!(folder_prefix("/home/u/"), "/home/u/");
+        assert_eq!(folder_prefix("/home/u"), "/home/u/");
+    }
+
+    #[test]
+    fn directory_counts_follow_adds_and_removes() {
+        let (_db, idx) = open();
+        let files = [meta("/a/b/c.txt", 10), meta("/a/b/d.txt", 20), meta("/a/e.txt", 5)];
+        apply(&idx, &files, true);
+        let top = idx.children("/");
+        assert_eq!(top.len(), 1);
+        assert_eq!((child(&top, "a").files, child(&top, "a").size, child(&top, "a").is_folder), (3, 35, true));
+        let a = idx.children("/a");
+        assert_eq!((child(&a, "b").files, child(&a, "b").size), (2, 30));
+        assert_eq!((child(&a, "e.txt").files, child(&a, "e.txt").is_folder), (1, false));
+        assert_eq!(idx.children("/A/B/").len(), 2);
+
+        apply(&idx, &files[..2], false);
+        let a = idx.children("/a");
+        assert_eq!(a.len(), 1, "an emptied folder leaves its parent's listing");
+        assert_eq!((child(&idx.children("/"), "a").files, child(&idx.children("/"), "a").size), (1, 5));
+        apply(&idx, &files[2..], false);
+        assert!(idx.children("/").is_empty());
+    }
+
+    #[test]
+    fn under_stops_at_folder_boundaries_and_with_prefix_does_not() {
+        let (_db, idx) = open();
+        apply(&idx, &[meta("/photos/a.jpg", 1), meta("/Photos/Sub/c.jpg", 2), meta("/photos2/b.jpg", 3)], true);
+        assert_eq!(paths(&idx.under("/photos")), vec!["/Photos/Sub/c.jpg", "/photos/a.jpg"]);
+        assert_eq!(paths(&idx.under("/PHOTOS/")), vec!["/Photos/Sub/c.jpg", "/photos/a.jpg"]);
+        assert_eq!(paths(&idx.with_prefix("/photos")).len(), 3);
+        assert_eq!(idx.under("/photos/sub")[0], ("h2".to_string(), "/Photos/Sub/c.jpg".to_string()));
+    }
+
+    fn walk(idx: &Indexes, db: &Db, sort_by: &str, limit: usize, descending: bool) -> Vec<Vec<String>> {
+        let mut pages = Vec::new();
+        let mut after: Option<String> = None;
+        loop {
+            let page = idx.page(db, sort_by, after.as_deref(), limit, descending).unwrap();
+            pages.push(page.entries.into_iter().map(|(_, p)| p).collect());
+            match page.next { Some(next) => after = Some(next), None => return pages }
+        }
+    }
+
+    #[test]
+    fn pages_walk_both_directions() {
+        let (db, idx) = open();
+        apply(&idx, &["/d", "/b", "/e", "/a", "/c"].map(|p| meta(p, p.len() as u64)), true);
+        assert_eq!(walk(&idx, &db, "path", 2, false), vec![vec!["/a", "/b"], vec!["/c", "/d"], vec!["/e"]]);
+        assert_eq!(walk(&idx, &db, "path", 2, true), vec![vec!["/e", "/d"], vec!["/c", "/b"], vec!["/a"]]);
+        assert_eq!(walk(&idx, &db, "name", 3, true), vec![vec!["/e", "/d", "/c"], vec!["/b", "/a"]]);
+    }
+
+    #[test]
+    fn a_page_that_ends_exactly_at_the_limit_has_no_cursor() {
+        let (db, idx) = open();
+        apply(&idx, &["/a", "/b", "/c", "/d"].map(|p| meta(p, 1)), true);
+        assert_eq!(walk(&idx, &db, "path", 2, false), vec![vec!["/a", "/b"], vec!["/c", "/d"]]);
+        assert_eq!(walk(&idx, &db, "path", 2, true), vec![vec!["/d", "/c"], vec!["/b", "/a"]]);
+        assert_eq!(walk(&idx, &db, "path", 4, false), vec![vec!["/a", "/b", "/c", "/d"]]);
+        let empty = open();
+        assert_eq!(walk(&empty.1, &empty.0, "size", 2, false), vec![Vec::<String>::new()]);
+    }
+}
@@ -0,0 +1,82 @@
+use serde::{Serialize, Deserialize};
+use sled::Db;
+use std::fs;
+use std::io::ErrorKind;
+
+use crate::FileMeta;
+
+// ── INTEGRITY REPORTS ──────────────────────────────────────────
+// A scrub re-hashes indexed files whose size and mtime still match the index.
+// Same stat with different bytes is silent corruption (bit rot, a bad sector);
+// a changed stat is an ordinary edit and is left for the next scan.
+// version_db: integrity::{timestamp}::{folder} → IntegrityReport
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IntegrityIssue {
+    pub path: String,
+    pub expected_hash: String,
+    pub actual_hash: Option<String>,    // corrupted files only
+    pub error: Option<String>,          // unreadable files only
+    pub recoverable: bool,              // the blob store still holds the expected bytes
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IntegrityReport {
+    pub folder_path: String,
+    pub timestamp: u64,
+    pub finished_at: u64,
+    pub verified: usize,    // re-hashed and matching
+    pub changed: usize,     // edited since the last scan, not checked
+    pub corrupted: Vec<IntegrityIssue>,
+    pub missing: Vec<IntegrityIssue>,
+    pub unreadable: Vec<IntegrityIssue>,
+}
+
+pub enum Outcome {
+    Verified,
+    Changed,
+    Corrupted(String),
+    Missing,
+    Unreadable(String),
+}
+
+// Checks one indexed location against the hash it is stored under
+pub fn check(hash: &str, meta: &FileMeta) -> Outcome {
+    let metadata = match fs::metadata(&meta.path) {
+        Ok(m) => m,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Outcome::Missing,
+        Err(e) => return Outcome::Unreadable(e.to_string()),
+    };
+    let modified = metadata.modified().map(|t| format!("{:?}", t)).unwrap_or_default();
+    if metadata.len() != meta.size || modified != meta.modified { return Outcome::Changed; }
+    match crate::calculate_hash(&meta.path) {
+        Ok(actual) if actual == hash => Outcome::Verified,
+        Ok(actual) => Outcome::Corrupted(actual),
+        Err(e) => Outcome::Unreadable(e),
+    }
+}
+
+fn report_key(timestamp: u64, folder: &str) -> String {
+    format!("integrity::{}::{}", timestamp, folder)
+}
+
+pub fn save(vdb: &Db, report: &IntegrityReport) -> Result<(), String> {
+    let encoded = serde_json::to_string(report).map_err(|e| e.to_string())?;
+    vdb.insert(report_key(report.timestamp, &report.folder_path).as_bytes(), encoded.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Newest first, optionally only for one folder
+pub fn list(vdb: &Db, folder: Option<&str>) -> Vec<IntegrityReport> {
+    let mut reports: Vec<IntegrityReport> = vdb.scan_prefix(b"integrity::")
+        .filter_map(|r| r.ok())
+        .filter_map(|(_, v)| serde_json::from_slice::<IntegrityReport>(&v).ok())
+        .filter(|r| folder.is_none_or(|f| r.folder_path == f))
+        .collect();
+    reports.sort_by_key(|r| std::cmp::Reverse(r.timestamp));
+    reports
+}
+
+pub fn delete(vdb: &Db, timestamp: u64, folder: &str) -> Result<bool, String> {
+    Ok(vdb.remove(report_key(timestamp, folder).as_bytes()).map_err(|e| e.to_string())?.is_some())
+}
@@ -0,0 +1,323 @@
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Condvar, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::progress::{new_job_id, JobProgress, ProgressEvent, Reporter};
+use crate::{now_ts, AppState};
+
+// ── JOB MANAGER ────────────────────────────────────────────────
+// Every long operation is a job: queued jobs wait for one of the runner threads,
+// inline jobs run on the calling command. Both are listed while live, and their
+// summaries are kept in version_db under job::{id} once they finish.
+// state: "queued" | "running" | "done" | "failed" | "cancelled"
+// The "job-updated" event for a finished job carries its full result; the stored
+// summary keeps counts in place of lists, and only the newest JOB_HISTORY_MAX are kept.
+
+pub const RUNNER_THREADS: usize = 2;
+const JOB_HISTORY_MAX: usize = 200;
+const STORED_TEXT_MAX: usize = 1000;
+const STORED_LOGS_MAX: usize = 100;
+
+pub type Task = Box<dyn FnOnce(&AppHandle, &JobProgress) -> Result<serde_json::Value, String> + Send>;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct JobSummary {
+    pub id: String,
+    pub kind: String,
+    pub label: String,
+    pub state: String,
+    pub created_at: u64,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    pub progress: Option<ProgressEvent>,
+    pub logs: Vec<String>,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+struct LiveJob {
+    summary: JobSummary,
+    progress: Arc<JobProgress>,
+    task: Option<Task>,
+}
+
+#[derive(Default)]
+pub struct JobManager {
+    live: Mutex<HashMap<String, LiveJob>>,
+    queue: Mutex<VecDeque<String>>,
+    wake: Condvar,
+}
+
+fn job_key(id: &str) -> String {
+    format!("job::{}", id)
+}
+
+// Lists become their length and long strings are cut, so a similar-images or scrub
+// result never lands in version_db whole
+fn condense(value: &serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::Array(items) => Value::from(items.len()),
+        Value::String(s) if s.len() > STORED_TEXT_MAX => {
+            let cut = (0..=STORED_TEXT_MAX).rev().find(|&i| s.is_char_boundary(i)).unwrap_or(0);
+            Value::from(format!("{}…", &s[..cut]))
+        }
+        Value::Object(fields) => Value::Object(fields.iter().map(|(k, v)| (k.clone(), condense(v))).collect()),
+        v => v.clone(),
+    }
+}
+
+fn stored_summary(summary: &JobSummary) -> JobSummary {
+    let mut stored = summary.clone();
+    stored.result = summary.result.as_ref().map(condense);
+    let skip = stored.logs.len().saturating_sub(STORED_LOGS_MAX);
+    stored.logs.drain(..skip);
+    stored
+}
+
+// Drops the oldest finished jobs beyond JOB_HISTORY_MAX
+fn trim_history(vdb: &sled::Db) -> Result<(), String> {
+    let mut finished: Vec<(Option<u64>, sled::IVec)> = vdb.scan_prefix(b"job::").filter_map(|r| r.ok())
+        .map(|(k, v)| (serde_json::from_slice::<JobSummary>(&v).ok().and_then(|j| j.finished_at), k))
+        .collect();
+    if finished.len() <= JOB_HISTORY_MAX { return Ok(()); }
+    finished.sort_by_key(|(at, _)| std::cmp::Reverse(*at));
+    for (_, key) in finished.drain(JOB_HISTORY_MAX..) { vdb.remove(key).map_err(|e| e.to_string())?; }
+    Ok(())
+}
+
+impl JobManager {
+    fn register(&self, id: String, kind: &str, label: String, state: &str, task: Option<Task>) -> Result<JobSummary, String> {
+        let now = now_ts();
+        let summary = JobSummary {
+            id: id.clone(),
+            kind: kind.to_string(),
+            label,
+            state: state.to_string(),
+            created_at: now,
+            started_at: (state == "running").then_some(now),
+            finished_at: None,
+            progress: None,
+            logs: Vec::new(),
+            result: None,
+            error: None,
+        };
+        let progress = Arc::new(JobProgress::new(id.clone(), kind));
+        let mut live = self.live.lock().map_err(|e| e.to_string())?;
+        if live.contains_key(&id) { return Err(format!("Job {} is already running", id)); }
+        live.insert(id, LiveJob { summary: summary.clone(), progress, task });
+        Ok(summary)
+    }
+
+    fn progress_of(&self, id: &str) -> Option<Arc<JobProgress>> {
+        self.live.lock().ok()?.get(id).map(|j| Arc::clone(&j.progress))
+    }
+
+    // Adds a job to the queue and returns its id straight away
+    pub fn enqueue(&self, app: &AppHandle, kind: &str, label: String, task: Task) -> Result<String, String> {
+        let id = new_job_id(kind);
+        let summary = self.register(id.clone(), kind, label, "queued", Some(task))?;
+        self.queue.lock().map_err(|e| e.to_string())?.push_back(id.clone());
+        self.wake.notify_one();
+        let _ = app.emit("job-updated", summary);
+        Ok(id)
+    }
+
+    // Runs `work` on the current thread as a tracked job
+    pub fn run_inline<T, F>(&self, app: &AppHandle, job_id: Option<String>, kind: &str, label: String, work: F) -> Result<T, String>
+    where
+        T: Serialize,
+        F: FnOnce(&JobProgress) -> Result<T, String>,
+    {
+        let id = job_id.unwrap_or_else(|| new_job_id(kind));
+        let summary = self.register(id.clone(), kind, label, "running", None)?;
+        let _ = app.emit("job-updated", summary);
+        let progress = self.progress_of(&id).ok_or("Job vanished")?;
+        let outcome = {
+            let _reporter = Reporter::start(app, &progress);
+            work(&progress)
+        };
+        let recorded = match &outcome {
+            Ok(value) => serde_json::to_value(value).map_err(|e| e.to_string()),
+            Err(e) => Err(e.clone()),
+        };
+        self.finish(app, &id, recorded);
+        outcome
+    }
+
+    fn start_next(&self, id: &str) -> Option<(Arc<JobProgress>, Option<Task>, JobSummary)> {
+        let mut live = self.live.lock().ok()?;
+        let job = live.get_mut(id)?;
+        job.summary.state = "running".to_string();
+        job.summary.started_at = Some(now_ts());
+        Some((Arc::clone(&job.progress), job.task.take(), job.summary.clone()))
+    }
+
+    fn finish(&self, app: &AppHandle, id: &str, outcome: Result<serde_json::Value, String>) {
+        let Some(job) = self.live.lock().ok().and_then(|mut live| live.remove(id)) else { return };
+        let mut summary = job.summary;
+        summary.state = match (&outcome, job.progress.is_cancelled()) {
+            (_, true) => "cancelled",
+            (Ok(_), false) => "done",
+            (Err(_), false) => "failed",
+        }.to_string();
+        summary.finished_at = Some(now_ts());
+        summary.progress = Some(job.progress.snapshot());
+        summary.logs = job.progress.logs();
+        match outcome {
+            Ok(value) => summary.result = Some(value),
+            Err(e) => summary.error = Some(e),
+        }
+
+        let state = app.state::<AppState>();
+        if let (Ok(vdb), Ok(json)) = (state.version_db.lock(), serde_json::to_string(&stored_summary(&summary))) {
+            let _ = vdb.insert(job_key(id).as_bytes(), json.as_bytes());
+            let _ = trim_history(&vdb);
+        }
+        let _ = app.emit("job-updated", summary);
+    }
+
+    // Queued jobs are skipped by the runner; running ones stop at their next check
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.progress_of(id) {
+            Some(progress) => { progress.cancel(); true }
+            None => false,
+        }
+    }
+
+    fn live_summaries(&self) -> Vec<JobSummary> {
+        let Ok(live) = self.live.lock() else { return Vec::new() };
+        live.values().map(|job| {
+            let mut summary = job.summary.clone();
+            summary.progress = Some(job.progress.snapshot());
+            summary.logs = job.progress.logs();
+            summary
+        }).collect()
+    }
+
+    // Live jobs first, then finished ones; newest first within each
+    pub fn list(&self, vdb: &sled::Db) -> Vec<JobSummary> {
+        let mut live = self.live_summaries();
+        live.sort_by_key(|j| std::cmp::Reverse(j.created_at));
+        let mut finished: Vec<JobSummary> = vdb.scan_prefix(b"job::").filter_map(|r| r.ok())
+            .filter_map(|(_, v)| serde_json::from_slice(&v).ok())
+            .collect();
+        finished.sort_by_key(|j| std::cmp::Reverse(j.finished_at));
+        live.extend(finished);
+        live
+    }
+
+    pub fn get(&self, vdb: &sled::Db, id: &str) -> Option<JobSummary> {
+        if let Some(job) = self.live_summaries().into_iter().find(|j| j.id == id) { return Some(job); }
+        let v = vdb.get(job_key(id).as_bytes()).ok()??;
+        serde_json::from_slice(&v).ok()
+    }
+
+    pub fn clear_finished(&self, vdb: &sled::Db) -> Result<(), String> {
+        let keys: Vec<_> = vdb.scan_prefix(b"job::").filter_map(|r| r.ok()).map(|(k, _)| k).collect();
+        for k in keys { vdb.remove(k).map_err(|e| e.to_string())?; }
+        Ok(())
+    }
+
+    fn next_queued(&self) -> Option<String> {
+        let mut queue = self.queue.lock().ok()?;
+        loop {
+            if let Some(id) = queue.pop_front() { return Some(id); }
+            queue = self.wake.wait(queue).ok()?;
+        }
+    }
+}
+
+// Runner threads live for the whole app and take queued jobs in FIFO order
+pub fn start_runners(app: &AppHandle, count: usize) {
+    for _ in 0..count.max(1) {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            let state = app.state::<AppState>();
+            let jobs = &state.jobs;
+            while let Some(id) = jobs.next_queued() {
+                let Some((progress, task, summary)) = jobs.start_next(&id) else { continue };
+                if progress.is_cancelled() {
+                    jobs.finish(&app, &id, Err("Cancelled before it started".to_string()));
+                    continue;
+                }
+                let _ = app.emit("job-updated", summary);
+                let outcome = {
+                    let _reporter = Reporter::start(&app, &progress);
+                    match task {
+                        Some(task) => task(&app, &progress),
+                        None => Err("Job has no work attached".to_string()),
+                    }
+                };
+                jobs.finish(&app, &id, outcome);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn summary(id: &str, finished_at: Option<u64>) -> JobSummary {
+        JobSummary {
+            id: id.to_string(), kind: "scan".to_string(), label: String::new(), state: "done".to_string(),
+            created_at: 0, started_at: None, finished_at, progress: None,
+            logs: Vec::new(), result: None, error: None,
+        }
+    }
+
+    #[test]
+    fn condense_counts_lists_and_keeps_scalars() {
+        let value = json!({ "groups": [[1, 2], [3]], "stats": { "files": [1, 2, 3], "bytes": 10 }, "ok": true, "note": null });
+        assert_eq!(condense(&value), json!({ "groups": 2, "stats": { "files": 3, "bytes": 10 }, "ok": true, "note": null }));
+        assert_eq!(condense(&json!([1, 2, 3, 4])), json!(4));
+        assert_eq!(condense(&json!("short")), json!("short"));
+    }
+
+    #[test]
+    fn condense_cuts_long_text_on_a_char_boundary() {
+        // 'é' is two bytes, so byte STORED_TEXT_MAX falls inside one
+        let long = format!("a{}", "é".repeat(STORED_TEXT_MAX));
+        let serde_json::Value::String(cut) = condense(&json!(long)) else { panic!("not a string") };
+        let kept = cut.strip_suffix('…').unwrap();
+        assert_eq!(kept.len(), STORED_TEXT_MAX - 1);
+        assert!(long.starts_with(kept));
+        let exact = "a".repeat(STORED_TEXT_MAX);
+        assert_eq!(condense(&json!(exact)), json!(exact));
+    }
+
+    #[test]
+    fn stored_summary_keeps_the_newest_logs() {
+        let mut job = summary("j", Some(1));
+        job.logs = (0..STORED_LOGS_MAX + 5).map(|i| i.to_string()).collect();
+        job.result = Some(json!({ "files": ["a", "b"] }));
+        let stored = stored_summary(&job);
+        assert_eq!(stored.logs.len(), STORED_LOGS_MAX);
+        assert_eq!(stored.logs[0], "5");
+        assert_eq!(stored.result, Some(json!({ "files": 2 })));
+    }
+
+    #[test]
+    fn trim_history_drops_the_oldest_finished_jobs() {
+        let vdb = sled::Config::new().temporary(true).open().unwrap();
+        let put = |job: &JobSummary| vdb.insert(job_key(&job.id), serde_json::to_vec(job).unwrap()).unwrap();
+        for i in 0..JOB_HISTORY_MAX as u64 { put(&summary(&format!("done-{}", i), Some(1000 + i))); }
+        trim_history(&vdb).unwrap();
+        assert_eq!(vdb.scan_prefix(b"job::").count(), JOB_HISTORY_MAX);
+
+        // An unfinished job sorts below every finished one and goes first, then the oldest
+        put(&summary("unfinished", None));
+        put(&summary("newest", Some(5000)));
+        vdb.insert(b"other", &b""[..]).unwrap();
+        trim_history(&vdb).unwrap();
+        assert_eq!(vdb.scan_prefix(b"job::").count(), JOB_HISTORY_MAX);
+        assert!(!vdb.contains_key(job_key("unfinished")).unwrap());
+        assert!(!vdb.contains_key(job_key("done-0")).unwrap());
+        assert!(vdb.contains_key(job_key("done-1")).unwrap());
+        assert!(vdb.contains_key(job_key("newest")).unwrap());
+        assert!(vdb.contains_key(b"other").unwrap());
+    }
+}
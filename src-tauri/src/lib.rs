@@ -1,22 +1,56 @@
 use blake3::Hasher;
 use sled::Db;
+use sled::transaction::{TransactionError, Transactional};
 use std::fs::{self, File};
 use std::io::{Read, Write};
-use std::sync::Mutex;
-use std::path::Path;
-use tauri::{Manager, State};
-use walkdir::WalkDir;
+use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, State};
 use serde::{Serialize, Deserialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+mod annotations;
+mod blobs;
+mod categories;
+mod filetype;
+mod fulltext;
+mod ignores;
+mod imagehash;
+mod indexes;
+mod integrity;
+mod jobs;
+mod progress;
+mod scan;
+mod schedule;
+mod search;
+mod similar;
+mod watch;
+
+use annotations::{Annotation, TagCount};
+use categories::{CategoryRule, CategoryRules};
+use fulltext::{ContentIndex, ContentPage};
+use ignores::IgnoreRules;
+use indexes::{DirChild, IndexTx, Indexes};
+use integrity::{IntegrityIssue, IntegrityReport, Outcome};
+use jobs::{JobManager, JobSummary};
+use progress::JobProgress;
+use scan::{ScanOptions, StatEntry};
+use schedule::RetentionPolicy;
+use search::{SearchHit, SearchPage};
+use imagehash::Algorithm;
+use similar::{HashMatch, HashThreshold, ImageIndex, SimilarGroup, SimilarPair};
+use watch::{FolderWatcher, FsChange};
+
 // ── STRUCTS ────────────────────────────────────────────────────
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct FileMeta {
     pub path: String,
     pub size: u64,
     pub modified: String,
     pub category: String,
+    #[serde(default)]
+    pub mime: String,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -28,6 +62,8 @@ pub struct DeletedEntry {
     pub category: String,
     pub deleted_at: u64,
     pub snapshot_name: String,
+    #[serde(default)]
+    pub blob_stored: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -36,6 +72,35 @@ pub struct SnapshotInfo {
     pub timestamp: u64,
     pub file_count: usize,
     pub folder_path: String,
+    #[serde(default)]
+    pub scheduled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FileVersion {
+    pub hash: String,
+    pub size: u64,
+    pub modified: String,
+    pub seen_at: u64,
+    pub blob_stored: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub hash: String,
+    pub size: u64,
+    pub modified: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DiffEntry {
+    pub change: String,   // "added" | "deleted" | "modified" | "moved"
+    pub path: String,
+    pub old_path: Option<String>,
+    pub hash: String,
+    pub old_hash: Option<String>,
+    pub size: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -46,7 +111,9 @@ pub struct FileProperties {
     pub hash: String,
     pub modified: String,
     pub category: String,
+    pub mime: String,
     pub exists_on_disk: bool,
+    pub locations: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -58,33 +125,80 @@ pub struct FolderProperties {
     pub exists_on_disk: bool,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TypeMismatch {
+    pub hash: String,
+    pub path: String,
+    pub extension: String,
+    pub extension_category: String,
+    pub mime: String,
+    pub category: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    pub size: u64,
+    pub category: String,
+    pub files: Vec<FileMeta>,
+    pub wasted_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FilePage {
+    pub items: Vec<SearchHit>,
+    pub next: Option<String>,      // cursor for the following page, None on the last one
+}
+
+// Payload of "index-changed": where each path touched by a watcher batch now stands
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IndexChange {
+    pub updated: Vec<SearchHit>,
+    pub removed: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WatchedFolder {
+    pub path: String,
+    pub added_at: u64,
+    #[serde(default)]
+    pub schedule: Option<String>,   // "hourly" | "daily" | "weekly"
+    #[serde(default)]
+    pub last_run: u64,
+}
+
 pub struct AppState {
     pub db: Mutex<Db>,
     pub version_db: Mutex<Db>,
+    pub data_dir: PathBuf,
+    pub jobs: JobManager,
+    pub watcher: FolderWatcher,
+    // Compiled settings::category_rules, filled on first use and replaced when the rules are saved
+    pub category_rules: Mutex<Option<Arc<CategoryRules>>>,
 }
 
 // ── HELPERS ────────────────────────────────────────────────────
 
-fn now_ts() -> u64 {
+pub(crate) fn now_ts() -> u64 {
     SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
 }
 
+// Content first, extension as the fallback; see filetype::detect
 fn get_category(path: &str) -> String {
-    let ext = path.rsplit('.').next().unwrap_or("").to_lowercase();
-    match ext.as_str() {
-        "jpg"|"jpeg"|"png"|"gif"|"webp"|"bmp"|"svg"|"ico" => "image",
-        "mp4"|"mkv"|"mov"|"avi"|"wmv"|"webm"|"flv"        => "video",
-        "pdf"|"doc"|"docx"|"txt"|"xlsx"|"xls"|"pptx"|"csv"|"md" => "document",
-        "mp3"|"wav"|"flac"|"aac"|"ogg"|"m4a"              => "audio",
-        "zip"|"rar"|"7z"|"tar"|"gz"|"bz2"                 => "archive",
-        "exe"|"msi"|"dmg"|"deb"                           => "executable",
-        _                                                   => "other",
-    }.to_string()
-}
-
-fn calculate_hash(path: &str) -> Result<String, String> {
+    filetype::detect(path).category
+}
+
+// Files above this size are memory-mapped and hashed across all cores
+const MMAP_HASH_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+pub(crate) fn calculate_hash(path: &str) -> Result<String, String> {
     let mut file = File::open(path).map_err(|e| e.to_string())?;
     let mut hasher = Hasher::new();
+    if file.metadata().map(|m| m.len() >= MMAP_HASH_THRESHOLD).unwrap_or(false) {
+        hasher.update_mmap_rayon(path).map_err(|e| e.to_string())?;
+        return Ok(hasher.finalize().to_string());
+    }
     let mut buffer = [0u8; 65536];
     loop {
         let n = file.read(&mut buffer).map_err(|e| e.to_string())?;
@@ -94,83 +208,362 @@ fn calculate_hash(path: &str) -> Result<String, String> {
     Ok(hasher.finalize().to_string())
 }
 
-fn index_single_path(path: &str, db: &Db) -> Result<String, String> {
+fn index_single_path(path: &str, state: &AppState) -> Result<String, String> {
     let p = Path::new(path);
     if !p.exists() { return Err(format!("Path does not exist: {}", path)); }
     let metadata = fs::metadata(p).map_err(|e| e.to_string())?;
     let size = metadata.len();
     let modified = format!("{:?}", metadata.modified().unwrap_or(SystemTime::now()));
-    let category = get_category(path);
+    let file_type = filetype::detect(path);
+    let category = category_rules(state)?.classify(path, size, &file_type);
     let hash = calculate_hash(path)?;
-    let meta = FileMeta { path: path.to_string(), size, modified, category };
-    let encoded = serde_json::to_string(&meta).map_err(|e| e.to_string())?;
-    db.insert(hash.as_bytes(), encoded.as_bytes()).map_err(|e| e.to_string())?;
+    let stat = StatEntry { size, modified: modified.clone(), inode: scan::inode_of(&metadata), hash: hash.clone(), file_type: Some(file_type.clone()) };
+    let meta = FileMeta { path: path.to_string(), size, modified, category, mime: file_type.mime.clone() };
+    let (content, images) = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        put_location(&db, &hash, &meta)?;
+        put_stat(&stat_cache_tree(&db)?, path, &stat)?;
+        (ContentIndex::open(&db)?, ImageIndex::open(&db)?)
+    };
+    // Unreadable text doesn't stop the file being indexed; it just won't match content searches
+    let _ = content.index_file(&hash, path, &file_type);
+    // Likewise an undecodable image is indexed, just never grouped as similar.
+    // Gated on what the file is, not on the category a user rule filed it under.
+    if file_type.category == "image" { let _ = images.index_file(&hash, path); }
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    track_version(&vdb, &blob_dir(state), &hash, &meta, &file_type)?;
     Ok(hash)
 }
 
-// ── PERCEPTUAL HASHING ─────────────────────────────────────────
-// DCT-based perceptual hash (pHash) — pure Rust, no C deps
-// Returns a 64-bit hash as u64
+fn file_name_of(path: &str) -> String {
+    path.split(|c| c == '/' || c == '\\').last().unwrap_or("").to_string()
+}
 
-fn perceptual_hash(path: &str) -> Result<u64, String> {
-    use image::imageops::FilterType;
-    use image::GenericImageView;
+fn snapshot_key(timestamp: u64, name: &str) -> String {
+    format!("snapshot::{}::{}", timestamp, name)
+}
 
-    let img = image::open(path).map_err(|e| e.to_string())?;
+// Each snapshot keeps its file list in its own tree: path → ManifestEntry
+fn manifest_tree_name(timestamp: u64, name: &str) -> String {
+    format!("manifest::{}::{}", timestamp, name)
+}
 
-    // Step 1: Resize to 32x32 grayscale for DCT
-    let small = img.resize_exact(32, 32, FilterType::Lanczos3)
-        .grayscale();
+fn manifest_tree(vdb: &Db, timestamp: u64, name: &str) -> Result<sled::Tree, String> {
+    vdb.open_tree(manifest_tree_name(timestamp, name)).map_err(|e| e.to_string())
+}
 
-    // Step 2: Build pixel matrix as f64
-    let mut pixels = [[0f64; 32]; 32];
-    for y in 0..32 {
-        for x in 0..32 {
-            let p = small.get_pixel(x as u32, y as u32);
-            pixels[y][x] = p[0] as f64;
-        }
+fn write_manifest(vdb: &Db, timestamp: u64, name: &str, entries: &[ManifestEntry]) -> Result<(), String> {
+    let tree = manifest_tree(vdb, timestamp, name)?;
+    let mut batch = sled::Batch::default();
+    for entry in entries {
+        let encoded = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+        batch.insert(entry.path.as_bytes(), encoded.as_bytes());
     }
+    tree.apply_batch(batch).map_err(|e| e.to_string())
+}
 
-    // Step 3: Apply 2D DCT (take top-left 8x8 coefficients)
-    let mut dct = [[0f64; 8]; 8];
-    for u in 0..8usize {
-        for v in 0..8usize {
-            let mut sum = 0f64;
-            for x in 0..32usize {
-                for y in 0..32usize {
-                    let cos_u = ((2.0 * x as f64 + 1.0) * u as f64 * std::f64::consts::PI / 64.0).cos();
-                    let cos_v = ((2.0 * y as f64 + 1.0) * v as f64 * std::f64::consts::PI / 64.0).cos();
-                    sum += pixels[y][x] * cos_u * cos_v;
-                }
-            }
-            let cu = if u == 0 { 1.0 / 2f64.sqrt() } else { 1.0 };
-            let cv = if v == 0 { 1.0 / 2f64.sqrt() } else { 1.0 };
-            dct[u][v] = (2.0 / 32.0) * cu * cv * sum;
-        }
+fn read_manifest(vdb: &Db, timestamp: u64, name: &str) -> Result<Vec<ManifestEntry>, String> {
+    if !vdb.contains_key(snapshot_key(timestamp, name).as_bytes()).map_err(|e| e.to_string())? {
+        return Err(format!("Snapshot not found: {}", name));
     }
+    Ok(manifest_tree(vdb, timestamp, name)?.iter()
+        .filter_map(|i| i.ok())
+        .filter_map(|(_, v)| serde_json::from_slice::<ManifestEntry>(&v).ok())
+        .collect())
+}
+
+fn read_snapshot_info(vdb: &Db, timestamp: u64, name: &str) -> Result<SnapshotInfo, String> {
+    let v = vdb.get(snapshot_key(timestamp, name).as_bytes()).map_err(|e| e.to_string())?
+        .ok_or(format!("Snapshot not found: {}", name))?;
+    serde_json::from_slice(&v).map_err(|e| e.to_string())
+}
+
+fn blob_dir(state: &AppState) -> PathBuf {
+    state.data_dir.join("blobs")
+}
+
+fn blob_store_enabled(vdb: &Db) -> bool {
+    matches!(vdb.get(b"settings::blob_store"), Ok(Some(v)) if v.as_ref() == b"true")
+}
+
+fn category_rule_list(vdb: &Db) -> Vec<CategoryRule> {
+    vdb.get(b"settings::category_rules").ok().flatten()
+        .and_then(|v| serde_json::from_slice(&v).ok())
+        .unwrap_or_default()
+}
+
+fn category_rules(state: &AppState) -> Result<Arc<CategoryRules>, String> {
+    let mut cached = state.category_rules.lock().map_err(|e| e.to_string())?;
+    if let Some(rules) = cached.as_ref() { return Ok(Arc::clone(rules)); }
+    let rules = { let vdb = state.version_db.lock().map_err(|e| e.to_string())?; category_rule_list(&vdb) };
+    let compiled = Arc::new(CategoryRules::compile(&rules)?);
+    *cached = Some(Arc::clone(&compiled));
+    Ok(compiled)
+}
+
+// What the bytes were detected as: from the stat cache, or rebuilt from the stored MIME
+fn detected_type(cache: &sled::Tree, meta: &FileMeta) -> filetype::FileType {
+    cache.get(meta.path.as_bytes()).ok().flatten()
+        .and_then(|v| serde_json::from_slice::<StatEntry>(&v).ok())
+        .and_then(|stat| stat.file_type)
+        .unwrap_or_else(|| filetype::from_mime(&meta.path, &meta.mime))
+}
+
+fn ignore_patterns(vdb: &Db) -> Vec<String> {
+    vdb.get(b"settings::ignore").ok().flatten()
+        .and_then(|v| serde_json::from_slice(&v).ok())
+        .unwrap_or_else(ignores::default_patterns)
+}
+
+fn ignore_rules(state: &AppState) -> Result<IgnoreRules, String> {
+    let patterns = { let vdb = state.version_db.lock().map_err(|e| e.to_string())?; ignore_patterns(&vdb) };
+    IgnoreRules::new(&patterns)
+}
 
-    // Step 4: Compute mean of DCT values (skip [0][0] DC component)
-    let mut values = Vec::with_capacity(63);
-    for u in 0..8 {
-        for v in 0..8 {
-            if u == 0 && v == 0 { continue; }
-            values.push(dct[u][v]);
+// Runs `delete` and leaves a tombstone for each of `entries` once it has succeeded, so a
+// failed delete leaves no history behind. With the blob store on, each file is copied in
+// first while it still exists. All of it happens under the version_db lock that retention's
+// blob GC holds, so the GC never sees a stored blob whose tombstone is not written yet;
+// copies left by a failed delete are unreferenced and go with the next collection.
+fn remove_recorded<F>(state: &AppState, mut entries: Vec<DeletedEntry>, delete: F) -> Result<(), String>
+where
+    F: FnOnce() -> Result<(), String>,
+{
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    if blob_store_enabled(&vdb) {
+        for entry in entries.iter_mut().filter(|e| Path::new(&e.path).is_file()) {
+            entry.hash = blobs::store(&blob_dir(state), Path::new(&entry.path))?;
+            entry.blob_stored = true;
         }
     }
-    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    delete()?;
+    for entry in &entries { record_deletion(&vdb, entry)?; }
+    Ok(())
+}
 
-    // Step 5: Build 64-bit hash — bit=1 if value > mean
-    let mut hash: u64 = 0;
-    for (i, &val) in values.iter().enumerate() {
-        if val > mean {
-            hash |= 1u64 << i;
-        }
+// A destination about to be replaced by a move, rename or extract keeps its bytes and leaves a tombstone
+fn preserve_overwritten(state: &AppState, path: &str) -> Result<(), String> {
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    {
+        let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+        if !blob_store_enabled(&vdb) || !Path::new(path).is_file() { return Ok(()); }
+        let hash = blobs::store(&blob_dir(state), Path::new(path))?;
+        let entry = DeletedEntry { hash, path: path.to_string(), name: file_name_of(path), size, category: get_category(path), deleted_at: now_ts(), snapshot_name: "overwritten".to_string(), blob_stored: true };
+        record_deletion(&vdb, &entry)?;
     }
-    Ok(hash)
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    remove_location(&db, path)?;
+    Ok(())
+}
+
+// ── VERSION CHAINS ─────────────────────────────────────────────
+// version::{path} → [FileVersion], oldest first. A new link is added whenever
+// the path is indexed with different contents than its latest version.
+
+fn version_key(path: &str) -> String {
+    format!("version::{}", path)
+}
+
+fn read_versions(vdb: &Db, path: &str) -> Vec<FileVersion> {
+    vdb.get(version_key(path).as_bytes()).ok().flatten()
+        .and_then(|v| serde_json::from_slice(&v).ok())
+        .unwrap_or_default()
+}
+
+fn write_versions(vdb: &Db, path: &str, chain: &[FileVersion]) -> Result<(), String> {
+    let encoded = serde_json::to_string(chain).map_err(|e| e.to_string())?;
+    vdb.insert(version_key(path).as_bytes(), encoded.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Documents get their contents copied per version whether or not the deleted-file
+// blob store is on; photo and video libraries would double in size for little benefit.
+// Decided by what the file is, so a user rule filing it elsewhere doesn't turn this off.
+fn keeps_version_contents(detected: &filetype::FileType) -> bool {
+    detected.category == "document"
+}
+
+fn track_version(vdb: &Db, blob_dir: &Path, hash: &str, meta: &FileMeta, detected: &filetype::FileType) -> Result<(), String> {
+    let mut chain = read_versions(vdb, &meta.path);
+    if chain.last().map(|v| v.hash == hash).unwrap_or(false) { return Ok(()); }
+    let blob_stored = chain.iter().any(|v| v.hash == hash && v.blob_stored)
+        || (keeps_version_contents(detected)
+            && blobs::store(blob_dir, Path::new(&meta.path)).map(|h| h == hash).unwrap_or(false));
+    if let Some(previous) = chain.last() { annotations::carry_forward(vdb, &previous.hash, hash)?; }
+    chain.push(FileVersion { hash: hash.to_string(), size: meta.size, modified: meta.modified.clone(), seen_at: now_ts(), blob_stored });
+    write_versions(vdb, &meta.path, &chain)
+}
+
+fn move_versions(vdb: &Db, old_path: &str, new_path: &str) -> Result<(), String> {
+    let Some(v) = vdb.remove(version_key(old_path).as_bytes()).map_err(|e| e.to_string())? else { return Ok(()) };
+    vdb.insert(version_key(new_path).as_bytes(), v).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn record_deletion(vdb: &Db, entry: &DeletedEntry) -> Result<(), String> {
+    let key = format!("deleted::{}::{}::{}", entry.deleted_at, entry.hash, entry.path);
+    let encoded = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    vdb.insert(key.as_bytes(), encoded.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Drops a location that vanished from disk, leaving a tombstone in history
+fn tombstone_location(state: &AppState, hash: &str, meta: &FileMeta, deleted_at: u64, snapshot_name: &str) -> Result<(), String> {
+    let entry = DeletedEntry { hash: hash.to_string(), path: meta.path.clone(), name: file_name_of(&meta.path), size: meta.size, category: meta.category.clone(), deleted_at, snapshot_name: snapshot_name.to_string(), blob_stored: false };
+    { let vdb = state.version_db.lock().map_err(|e| e.to_string())?; record_deletion(&vdb, &entry)?; }
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    remove_location(&db, &meta.path)?;
+    Ok(())
+}
+
+// ── LOCATION INDEX ─────────────────────────────────────────────
+// Primary tree: "{hash}::{path}" → FileMeta, one entry per copy on disk.
+// Reverse tree "paths": path → hash, so a location can be found without a scan.
+// Folder, category and directory-listing indexes (indexes.rs) are written in the
+// same transaction as these two.
+
+fn location_key(hash: &str, path: &str) -> String {
+    format!("{}::{}", hash, path)
+}
+
+fn paths_tree(db: &Db) -> Result<sled::Tree, String> {
+    db.open_tree("paths").map_err(|e| e.to_string())
+}
+
+fn put_location(db: &Db, hash: &str, meta: &FileMeta) -> Result<(), String> {
+    put_locations(db, &[(hash.to_string(), meta.clone())])
+}
+
+// Writes many locations in one transaction; a path whose contents changed
+// drops its entry under the old hash.
+fn put_locations(db: &Db, entries: &[(String, FileMeta)]) -> Result<(), String> {
+    let paths = paths_tree(db)?;
+    let idx = Indexes::open(db)?;
+    let encoded = entries.iter().map(|(_, m)| serde_json::to_string(m)).collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?;
+    (&**db, &paths, &idx.by_path, &idx.by_category, &idx.dirs, &idx.sorted).transaction(|(primary, paths, by_path, by_category, dirs, sorted)| {
+        let tx = IndexTx { by_path, by_category, dirs, sorted };
+        for ((hash, meta), enc) in entries.iter().zip(&encoded) {
+            if let Some(old) = paths.get(meta.path.as_bytes())? {
+                let old_hash = String::from_utf8_lossy(&old).to_string();
+                let previous = primary.remove(location_key(&old_hash, &meta.path).as_bytes())?;
+                if let Some(prev) = previous.and_then(|v| serde_json::from_slice::<FileMeta>(&v).ok()) { indexes::remove(&tx, &prev)?; }
+            }
+            primary.insert(location_key(hash, &meta.path).as_bytes(), enc.as_bytes())?;
+            paths.insert(meta.path.as_bytes(), hash.as_bytes())?;
+            indexes::add(&tx, hash, meta)?;
+        }
+        Ok(())
+    }).map_err(|e: TransactionError<String>| e.to_string())
+}
+
+// True when `meta.path` is already indexed under `hash` with exactly this metadata
+fn is_indexed_as(db: &Db, paths: &sled::Tree, hash: &str, meta: &FileMeta) -> bool {
+    paths.get(meta.path.as_bytes()).ok().flatten().is_some_and(|h| h == hash.as_bytes())
+        && get_location(db, hash, &meta.path).is_ok_and(|m| m == *meta)
+}
+
+fn remove_location(db: &Db, path: &str) -> Result<Option<(String, FileMeta)>, String> {
+    stat_cache_tree(db)?.remove(path.as_bytes()).map_err(|e| e.to_string())?;
+    let paths = paths_tree(db)?;
+    let idx = Indexes::open(db)?;
+    (&**db, &paths, &idx.by_path, &idx.by_category, &idx.dirs, &idx.sorted).transaction(|(primary, paths, by_path, by_category, dirs, sorted)| {
+        let Some(h) = paths.remove(path.as_bytes())? else { return Ok(None) };
+        let hash = String::from_utf8_lossy(&h).to_string();
+        let removed = primary.remove(location_key(&hash, path).as_bytes())?.and_then(|v| serde_json::from_slice::<FileMeta>(&v).ok());
+        if let Some(meta) = &removed { indexes::remove(&IndexTx { by_path, by_category, dirs, sorted }, meta)?; }
+        Ok(removed.map(|m| (hash, m)))
+    }).map_err(|e: TransactionError<String>| e.to_string())
+}
+
+// Re-keys one location to `new_path`, carrying its stat cache entry so the
+// move doesn't cost a re-hash
+fn move_location(db: &Db, path: &str, new_path: &str) -> Result<Option<(String, FileMeta)>, String> {
+    let cache = stat_cache_tree(db)?;
+    let stat = cache.get(path.as_bytes()).map_err(|e| e.to_string())?;
+    let Some((hash, mut meta)) = remove_location(db, path)? else { return Ok(None) };
+    meta.path = new_path.to_string();
+    put_location(db, &hash, &meta)?;
+    if let Some(stat) = stat { cache.insert(new_path.as_bytes(), stat).map_err(|e| e.to_string())?; }
+    Ok(Some((hash, meta)))
+}
+
+fn get_location(db: &Db, hash: &str, path: &str) -> Result<FileMeta, String> {
+    let v = db.get(location_key(hash, path).as_bytes()).map_err(|e| e.to_string())?.ok_or("Location not found")?;
+    serde_json::from_slice(&v).map_err(|e| e.to_string())
+}
+
+fn hash_locations(db: &Db, hash: &str) -> Vec<FileMeta> {
+    db.scan_prefix(format!("{}::", hash).as_bytes())
+        .filter_map(|i| i.ok())
+        .filter_map(|(_, v)| serde_json::from_slice::<FileMeta>(&v).ok())
+        .collect()
 }
 
-fn hamming_distance(a: u64, b: u64) -> u32 {
-    (a ^ b).count_ones()
+fn all_locations(db: &Db) -> Vec<(String, FileMeta)> {
+    db.iter()
+        .filter_map(|i| i.ok())
+        .filter_map(|(k, v)| {
+            let key = String::from_utf8_lossy(&k).to_string();
+            let hash = key.split("::").next()?.to_string();
+            serde_json::from_slice::<FileMeta>(&v).ok().map(|m| (hash, m))
+        })
+        .collect()
+}
+
+fn resolve(db: &Db, pairs: Vec<(String, String)>) -> Vec<(String, FileMeta)> {
+    pairs.into_iter().filter_map(|(hash, path)| get_location(db, &hash, &path).ok().map(|m| (hash, m))).collect()
+}
+
+// Everything inside `folder_path`, read from the by_path index
+fn locations_under(db: &Db, folder_path: &str) -> Vec<(String, FileMeta)> {
+    Indexes::open(db).map(|idx| resolve(db, idx.under(folder_path))).unwrap_or_default()
+}
+
+fn locations_in_category(db: &Db, category: &str) -> Vec<(String, FileMeta)> {
+    Indexes::open(db).map(|idx| resolve(db, idx.in_category(category))).unwrap_or_default()
+}
+
+// ── STAT CACHE ─────────────────────────────────────────────────
+// "stat_cache" tree: path → StatEntry, consulted by scan::cached_stat
+
+fn stat_cache_tree(db: &Db) -> Result<sled::Tree, String> {
+    db.open_tree("stat_cache").map_err(|e| e.to_string())
+}
+
+fn put_stat(cache: &sled::Tree, path: &str, stat: &StatEntry) -> Result<(), String> {
+    let encoded = serde_json::to_string(stat).map_err(|e| e.to_string())?;
+    cache.insert(path.as_bytes(), encoded.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// `path` re-rooted from `old_root` to `new_root`. Folder lookups match case-insensitively,
+// so a root that differs from the stored path only in case is skipped by components.
+fn rebase_path(path: &str, old_root: &str, new_root: &str) -> String {
+    let p = Path::new(path);
+    let rel: PathBuf = match p.strip_prefix(old_root) {
+        Ok(rel) => rel.to_path_buf(),
+        Err(_) => p.components().skip(Path::new(old_root).components().count()).collect(),
+    };
+    Path::new(new_root).join(rel).to_string_lossy().to_string()
+}
+
+// Moves every location under `old_path` along with its stat cache entry and version chain.
+// Returns the (old, new) path of each one moved.
+fn relocate_folder(state: &AppState, old_path: &str, new_path: &str) -> Result<Vec<(String, String)>, String> {
+    let moved: Vec<(String, String)> = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let mut moved = Vec::new();
+        for (_, meta) in locations_under(&db, old_path) {
+            let new = rebase_path(&meta.path, old_path, new_path);
+            move_location(&db, &meta.path, &new)?;
+            moved.push((meta.path, new));
+        }
+        db.flush().map_err(|e| e.to_string())?;
+        moved
+    };
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    for (from, to) in &moved { move_versions(&vdb, from, to)?; }
+    Ok(moved)
 }
 
 // ── FILE COMMANDS ──────────────────────────────────────────────
@@ -192,41 +585,46 @@ fn check_file_status(path: String) -> bool {
 }
 
 #[tauri::command]
-fn get_file_properties(hash: String, state: State<'_, AppState>) -> Result<FileProperties, String> {
+fn get_file_properties(hash: String, path: String, state: State<'_, AppState>) -> Result<FileProperties, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
-    let v = db.get(hash.as_bytes()).map_err(|e| e.to_string())?.ok_or("Hash not found")?;
-    let meta: FileMeta = serde_json::from_slice(&v).map_err(|e| e.to_string())?;
-    let name = meta.path.split(|c| c == '/' || c == '\\').last().unwrap_or("").to_string();
+    let meta = get_location(&db, &hash, &path)?;
+    let name = file_name_of(&meta.path);
     let exists = Path::new(&meta.path).exists();
-    Ok(FileProperties { path: meta.path, name, size: meta.size, hash, modified: meta.modified, category: meta.category, exists_on_disk: exists })
+    let locations = hash_locations(&db, &hash).into_iter().map(|m| m.path).collect();
+    Ok(FileProperties { path: meta.path, name, size: meta.size, hash, modified: meta.modified, category: meta.category, mime: meta.mime, exists_on_disk: exists, locations })
 }
 
 #[tauri::command]
 fn get_folder_properties(folder_path: String, state: State<'_, AppState>) -> Result<FolderProperties, String> {
+    let rules = ignore_rules(&state)?;
+    let roots = watch_root_paths(&state);
+    let root = watch_root_of(&roots, &folder_path).unwrap_or(Path::new(&folder_path));
     let db = state.db.lock().map_err(|e| e.to_string())?;
-    let norm = folder_path.replace('\\', "/").to_lowercase();
-    let name = folder_path.split(|c| c == '/' || c == '\\').last().unwrap_or("").to_string();
+    let name = file_name_of(&folder_path);
     let exists = Path::new(&folder_path).exists();
     let mut file_count = 0usize;
     let mut total_size = 0u64;
-    for item in db.iter() {
-        if let Ok((_, v)) = item {
-            if let Ok(meta) = serde_json::from_slice::<FileMeta>(&v) {
-                if meta.path.replace('\\', "/").to_lowercase().starts_with(&norm) {
-                    file_count += 1;
-                    total_size += meta.size;
-                }
-            }
-        }
+    for (_, meta) in locations_under(&db, &folder_path) {
+        if rules.is_ignored(root, Path::new(&meta.path), false) { continue; }
+        file_count += 1;
+        total_size += meta.size;
     }
     Ok(FolderProperties { path: folder_path, name, file_count, total_size, exists_on_disk: exists })
 }
 
+// Immediate children of a folder from the dir_children index, folders first
 #[tauri::command]
-fn add_single_file(path: String, state: State<'_, AppState>) -> Result<String, String> {
+fn list_folder(folder_path: String, state: State<'_, AppState>) -> Result<Vec<DirChild>, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
-    let hash = index_single_path(&path, &db)?;
-    db.flush().map_err(|e| e.to_string())?;
+    let mut children = Indexes::open(&db)?.children(&folder_path);
+    children.sort_by(|a, b| b.is_folder.cmp(&a.is_folder).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+    Ok(children)
+}
+
+#[tauri::command]
+fn add_single_file(path: String, state: State<'_, AppState>) -> Result<String, String> {
+    let hash = index_single_path(&path, &state)?;
+    state.db.lock().map_err(|e| e.to_string())?.flush().map_err(|e| e.to_string())?;
     let name = path.split(|c| c == '/' || c == '\\').last().unwrap_or("");
     Ok(format!("Added: {} ({})", name, &hash[..12]))
 }
@@ -234,17 +632,18 @@ fn add_single_file(path: String, state: State<'_, AppState>) -> Result<String, S
 // ── RENAME ─────────────────────────────────────────────────────
 
 #[tauri::command]
-fn rename_in_index(hash: String, new_name: String, state: State<'_, AppState>) -> Result<(), String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let v = db.get(hash.as_bytes()).map_err(|e| e.to_string())?.ok_or("Hash not found")?;
-    let mut meta: FileMeta = serde_json::from_slice(&v).map_err(|e| e.to_string())?;
-    let old_path = Path::new(&meta.path);
+fn rename_in_index(hash: String, path: String, new_name: String, state: State<'_, AppState>) -> Result<(), String> {
+    let old_path = Path::new(&path);
     let new_path = old_path.parent().ok_or("No parent")?.join(&new_name);
+    if new_path != old_path && new_path.is_file() { preserve_overwritten(&state, &new_path.to_string_lossy())?; }
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    get_location(&db, &hash, &path)?;
     if old_path.exists() { fs::rename(old_path, &new_path).map_err(|e| e.to_string())?; }
-    meta.path = new_path.to_string_lossy().to_string();
-    let encoded = serde_json::to_string(&meta).map_err(|e| e.to_string())?;
-    db.insert(hash.as_bytes(), encoded.as_bytes()).map_err(|e| e.to_string())?;
+    let new_str = new_path.to_string_lossy().to_string();
+    move_location(&db, &path, &new_str)?;
     db.flush().map_err(|e| e.to_string())?;
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    move_versions(&vdb, &path, &new_str)?;
     Ok(())
 }
 
@@ -254,20 +653,7 @@ fn rename_folder(old_path: String, new_name: String, state: State<'_, AppState>)
     let new_path = old.parent().ok_or("No parent")?.join(&new_name);
     if old.exists() { fs::rename(old, &new_path).map_err(|e| e.to_string())?; }
     let new_str = new_path.to_string_lossy().to_string();
-    let old_norm = old_path.replace('\\', "/").to_lowercase();
-    let new_norm = new_str.replace('\\', "/");
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let to_update: Vec<(String, FileMeta)> = db.iter()
-        .filter_map(|i| i.ok())
-        .filter_map(|(k, v)| { let h = String::from_utf8_lossy(&k).to_string(); serde_json::from_slice::<FileMeta>(&v).ok().map(|m| (h, m)) })
-        .filter(|(_, m)| m.path.replace('\\', "/").to_lowercase().starts_with(&old_norm))
-        .collect();
-    for (hash, mut meta) in to_update {
-        let rel = meta.path.replace('\\', "/")[old_norm.len()..].to_string();
-        meta.path = format!("{}{}", new_norm, rel);
-        db.insert(hash.as_bytes(), serde_json::to_string(&meta).unwrap().as_bytes()).unwrap();
-    }
-    db.flush().map_err(|e| e.to_string())?;
+    relocate_folder(&state, &old_path, &new_str)?;
     Ok(new_str)
 }
 
@@ -275,120 +661,137 @@ fn rename_folder(old_path: String, new_name: String, state: State<'_, AppState>)
 
 #[tauri::command]
 fn delete_to_bin(hash: String, path: String, state: State<'_, AppState>) -> Result<(), String> {
-    let name = path.split(|c| c == '/' || c == '\\').last().unwrap_or("unknown").to_string();
+    let name = file_name_of(&path);
     let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
     let category = get_category(&path);
-    let entry = DeletedEntry { hash: hash.clone(), path: path.clone(), name, size, category, deleted_at: now_ts(), snapshot_name: "manual".to_string() };
-    let del_key = format!("deleted::{}::{}", now_ts(), hash);
-    { let vdb = state.version_db.lock().map_err(|e| e.to_string())?; vdb.insert(del_key.as_bytes(), serde_json::to_string(&entry).unwrap().as_bytes()).unwrap(); }
-    if Path::new(&path).exists() { trash::delete(&path).map_err(|e| e.to_string())?; }
-    state.db.lock().map_err(|e| e.to_string())?.remove(hash.as_bytes()).map_err(|e| e.to_string())?;
+    let entry = DeletedEntry { hash, path: path.clone(), name, size, category, deleted_at: now_ts(), snapshot_name: "manual".to_string(), blob_stored: false };
+    remove_recorded(&state, vec![entry], || {
+        if Path::new(&path).exists() { trash::delete(&path).map_err(|e| e.to_string())?; }
+        Ok(())
+    })?;
+    { let db = state.db.lock().map_err(|e| e.to_string())?; remove_location(&db, &path)?; }
     Ok(())
 }
 
 #[tauri::command]
 fn delete_folder_to_bin(folder_path: String, state: State<'_, AppState>) -> Result<String, String> {
-    let norm = folder_path.replace('\\', "/").to_lowercase();
-    let hashes: Vec<String> = {
-        let db = state.db.lock().map_err(|e| e.to_string())?;
-        db.iter().filter_map(|i| i.ok()).filter_map(|(k, v)| {
-            let hash = String::from_utf8_lossy(&k).to_string();
-            serde_json::from_slice::<FileMeta>(&v).ok()
-                .filter(|m| m.path.replace('\\', "/").to_lowercase().starts_with(&norm))
-                .map(|_| hash)
-        }).collect()
-    };
-    let count = hashes.len();
-    if Path::new(&folder_path).exists() { trash::delete(&folder_path).map_err(|e| e.to_string())?; }
+    let files = { let db = state.db.lock().map_err(|e| e.to_string())?; locations_under(&db, &folder_path) };
+    let count = files.len();
+    // Every indexed file gets the same copy + tombstone a single delete_to_bin would leave
+    let deleted_at = now_ts();
+    let entries = files.iter().map(|(hash, meta)| DeletedEntry { hash: hash.clone(), path: meta.path.clone(), name: file_name_of(&meta.path), size: meta.size, category: meta.category.clone(), deleted_at, snapshot_name: "manual".to_string(), blob_stored: false }).collect();
+    remove_recorded(&state, entries, || {
+        if Path::new(&folder_path).exists() { trash::delete(&folder_path).map_err(|e| e.to_string())?; }
+        Ok(())
+    })?;
     let db = state.db.lock().map_err(|e| e.to_string())?;
-    for hash in hashes { db.remove(hash.as_bytes()).unwrap(); }
+    for (_, meta) in files { remove_location(&db, &meta.path)?; }
     db.flush().map_err(|e| e.to_string())?;
     Ok(format!("Moved {} files to Recycle Bin.", count))
 }
 
 #[tauri::command]
 fn delete_physical_file(hash: String, path: String, state: State<'_, AppState>) -> Result<(), String> {
-    let name = path.split(|c| c == '/' || c == '\\').last().unwrap_or("unknown").to_string();
+    let name = file_name_of(&path);
     let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
-    let entry = DeletedEntry { hash: hash.clone(), path: path.clone(), name, size, category: get_category(&path), deleted_at: now_ts(), snapshot_name: "permanent".to_string() };
-    { let vdb = state.version_db.lock().map_err(|e| e.to_string())?; vdb.insert(format!("deleted::{}::{}", now_ts(), hash).as_bytes(), serde_json::to_string(&entry).unwrap().as_bytes()).unwrap(); }
-    if Path::new(&path).exists() { fs::remove_file(&path).map_err(|e| e.to_string())?; }
-    state.db.lock().map_err(|e| e.to_string())?.remove(hash.as_bytes()).map_err(|e| e.to_string())?;
+    let entry = DeletedEntry { hash, path: path.clone(), name, size, category: get_category(&path), deleted_at: now_ts(), snapshot_name: "permanent".to_string(), blob_stored: false };
+    remove_recorded(&state, vec![entry], || {
+        if Path::new(&path).exists() { fs::remove_file(&path).map_err(|e| e.to_string())?; }
+        Ok(())
+    })?;
+    { let db = state.db.lock().map_err(|e| e.to_string())?; remove_location(&db, &path)?; }
     Ok(())
 }
 
 // ── MOVE / PASTE ────────────────────────────────────────────────
 
 #[tauri::command]
-fn move_file(hash: String, destination_folder: String, state: State<'_, AppState>) -> Result<String, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let v = db.get(hash.as_bytes()).map_err(|e| e.to_string())?.ok_or("Hash not found")?;
-    let mut meta: FileMeta = serde_json::from_slice(&v).map_err(|e| e.to_string())?;
-    let file_name = Path::new(&meta.path).file_name().ok_or("No filename")?.to_string_lossy().to_string();
+fn move_file(hash: String, path: String, destination_folder: String, state: State<'_, AppState>) -> Result<String, String> {
+    let file_name = Path::new(&path).file_name().ok_or("No filename")?.to_string_lossy().to_string();
     let new_path = Path::new(&destination_folder).join(&file_name);
-    if Path::new(&meta.path).exists() { fs::rename(&meta.path, &new_path).map_err(|e| e.to_string())?; }
-    meta.path = new_path.to_string_lossy().to_string();
-    db.insert(hash.as_bytes(), serde_json::to_string(&meta).unwrap().as_bytes()).map_err(|e| e.to_string())?;
+    if new_path != Path::new(&path) && new_path.is_file() { preserve_overwritten(&state, &new_path.to_string_lossy())?; }
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    get_location(&db, &hash, &path)?;
+    if Path::new(&path).exists() { fs::rename(&path, &new_path).map_err(|e| e.to_string())?; }
+    let new_str = new_path.to_string_lossy().to_string();
+    move_location(&db, &path, &new_str)?;
     db.flush().map_err(|e| e.to_string())?;
-    Ok(meta.path)
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    move_versions(&vdb, &path, &new_str)?;
+    Ok(new_str)
 }
 
-#[tauri::command]
-fn move_folder(old_path: String, destination_parent: String, state: State<'_, AppState>) -> Result<String, String> {
+fn relocate_folder_on_disk(state: &AppState, old_path: &str, destination_parent: &str) -> Result<String, String> {
     let folder_name = Path::new(&old_path).file_name().ok_or("No folder name")?.to_string_lossy().to_string();
     let new_path = Path::new(&destination_parent).join(&folder_name);
-    if Path::new(&old_path).exists() { fs::rename(&old_path, &new_path).map_err(|e| e.to_string())?; }
+    if Path::new(old_path).exists() { fs::rename(old_path, &new_path).map_err(|e| e.to_string())?; }
     let new_str = new_path.to_string_lossy().to_string();
-    let old_norm = old_path.replace('\\', "/").to_lowercase();
-    let new_norm = new_str.replace('\\', "/");
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let to_update: Vec<(String, FileMeta)> = db.iter()
-        .filter_map(|i| i.ok())
-        .filter_map(|(k, v)| { let h = String::from_utf8_lossy(&k).to_string(); serde_json::from_slice::<FileMeta>(&v).ok().map(|m| (h, m)) })
-        .filter(|(_, m)| m.path.replace('\\', "/").to_lowercase().starts_with(&old_norm))
-        .collect();
-    for (hash, mut meta) in to_update {
-        let rel = meta.path.replace('\\', "/")[old_norm.len()..].to_string();
-        meta.path = format!("{}{}", new_norm, rel);
-        db.insert(hash.as_bytes(), serde_json::to_string(&meta).unwrap().as_bytes()).unwrap();
-    }
-    db.flush().map_err(|e| e.to_string())?;
+    relocate_folder(state, old_path, &new_str)?;
     Ok(new_str)
 }
 
+#[tauri::command]
+fn move_folder(old_path: String, destination_parent: String, app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+    let label = format!("Move {} to {}", old_path, destination_parent);
+    state.jobs.run_inline(&app, None, "move", label, |_| relocate_folder_on_disk(&state, &old_path, &destination_parent))
+}
+
 // ── COMPRESS / EXTRACT ─────────────────────────────────────────
 
-#[tauri::command]
-fn compress_to_zip(paths: Vec<String>, output_path: String) -> Result<String, String> {
-    let file = File::create(&output_path).map_err(|e| e.to_string())?;
-    let mut zip = zip::ZipWriter::new(file);
-    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
-    for path_str in &paths {
+// (source file, name inside the archive) for every file under `paths`
+fn zip_entries(paths: &[String], rules: &IgnoreRules) -> Vec<(PathBuf, String)> {
+    let mut entries = Vec::new();
+    for path_str in paths {
         let path = Path::new(path_str);
         if path.is_file() {
-            let name = path.file_name().unwrap_or_default().to_string_lossy();
-            zip.start_file(name.as_ref(), options).map_err(|e| e.to_string())?;
-            let mut buf = Vec::new();
-            File::open(path).map_err(|e| e.to_string())?.read_to_end(&mut buf).map_err(|e| e.to_string())?;
-            zip.write_all(&buf).map_err(|e| e.to_string())?;
+            entries.push((path.to_path_buf(), path.file_name().unwrap_or_default().to_string_lossy().to_string()));
         } else if path.is_dir() {
-            for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+            for entry in ignores::walk(path, rules) {
                 if entry.file_type().is_file() {
-                    let rel = entry.path().strip_prefix(path.parent().unwrap_or(path)).unwrap_or(entry.path()).to_string_lossy();
-                    zip.start_file(rel.as_ref(), options).map_err(|e| e.to_string())?;
-                    let mut buf = Vec::new();
-                    File::open(entry.path()).map_err(|e| e.to_string())?.read_to_end(&mut buf).map_err(|e| e.to_string())?;
-                    zip.write_all(&buf).map_err(|e| e.to_string())?;
+                    let rel = entry.path().strip_prefix(path.parent().unwrap_or(path)).unwrap_or(entry.path()).to_string_lossy().to_string();
+                    entries.push((entry.path().to_path_buf(), rel));
                 }
             }
         }
     }
+    entries
+}
+
+fn write_zip(paths: &[String], output_path: &str, rules: &IgnoreRules, progress: &JobProgress) -> Result<String, String> {
+    let entries = zip_entries(paths, rules);
+    for (src, _) in &entries { progress.discovered(fs::metadata(src).map(|m| m.len()).unwrap_or(0)); }
+    progress.discovery_done();
+
+    let file = File::create(output_path).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (src, name) in &entries {
+        if progress.is_cancelled() {
+            drop(zip);
+            let _ = fs::remove_file(output_path);
+            return Err("Compression cancelled".to_string());
+        }
+        progress.working_on(&src.to_string_lossy());
+        zip.start_file(name.as_str(), options).map_err(|e| e.to_string())?;
+        let mut buf = Vec::new();
+        File::open(src).map_err(|e| e.to_string())?.read_to_end(&mut buf).map_err(|e| e.to_string())?;
+        zip.write_all(&buf).map_err(|e| e.to_string())?;
+        progress.processed(buf.len() as u64, false);
+    }
     zip.finish().map_err(|e| e.to_string())?;
+    progress.log(format!("Wrote {} files", entries.len()));
     Ok(format!("Compressed to: {}", output_path))
 }
 
 #[tauri::command]
-fn extract_zip(zip_path: String, output_dir: String) -> Result<String, String> {
+fn compress_to_zip(paths: Vec<String>, output_path: String, app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+    let label = format!("Compress to {}", output_path);
+    let rules = ignore_rules(&state)?;
+    state.jobs.run_inline(&app, None, "zip", label, |progress| write_zip(&paths, &output_path, &rules, progress))
+}
+
+#[tauri::command]
+fn extract_zip(zip_path: String, output_dir: String, state: State<'_, AppState>) -> Result<String, String> {
     let file = File::open(&zip_path).map_err(|e| e.to_string())?;
     let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
     let total = archive.len();
@@ -401,196 +804,997 @@ fn extract_zip(zip_path: String, output_dir: String) -> Result<String, String> {
             if let Some(p) = out.parent() { fs::create_dir_all(p).map_err(|e| e.to_string())?; }
             let mut buf = Vec::new();
             entry.read_to_end(&mut buf).map_err(|e| e.to_string())?;
+            if out.is_file() { preserve_overwritten(&state, &out.to_string_lossy())?; }
             File::create(&out).map_err(|e| e.to_string())?.write_all(&buf).map_err(|e| e.to_string())?;
         }
     }
     Ok(format!("Extracted {} files to: {}", total, output_dir))
 }
 
-// ── SMART DEDUP — PERCEPTUAL HASHING ──────────────────────────
-// Returns: Vec of (best_hash, [similar_hashes], similarity_pct)
+// ── EXACT DUPLICATES ───────────────────────────────────────────
+// Groups every location sharing a BLAKE3 hash. sort_by: "wasted" (default), "count", "size"
 
 #[tauri::command]
-async fn find_similar_images(
-    threshold: u32,           // max hamming distance (0=identical, 64=totally different)
-    state: State<'_, AppState>,
-) -> Result<Vec<(String, Vec<String>, u32)>, String> {
-    // Collect all image files from vault
-    let images: Vec<(String, String)> = {
-        let db = state.db.lock().map_err(|e| e.to_string())?;
-        db.iter()
-            .filter_map(|i| i.ok())
-            .filter_map(|(k, v)| {
-                let hash = String::from_utf8_lossy(&k).to_string();
-                serde_json::from_slice::<FileMeta>(&v).ok()
-                    .filter(|m| m.category == "image" && Path::new(&m.path).exists())
-                    .map(|m| (hash, m.path))
-            })
-            .collect()
-    };
-
-    if images.is_empty() { return Ok(vec![]); }
-
-    // Compute perceptual hashes for all images
-    let mut phashes: Vec<(String, String, u64)> = Vec::new(); // (blake_hash, path, phash)
-    for (blake_hash, path) in &images {
-        if let Ok(ph) = perceptual_hash(path) {
-            phashes.push((blake_hash.clone(), path.clone(), ph));
-        }
+fn find_exact_duplicates(sort_by: Option<String>, state: State<'_, AppState>) -> Result<Vec<DuplicateGroup>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let mut by_hash: std::collections::BTreeMap<String, Vec<FileMeta>> = std::collections::BTreeMap::new();
+    for (hash, meta) in all_locations(&db) {
+        by_hash.entry(hash).or_default().push(meta);
     }
 
-    // Find groups using union-find style grouping
-    let n = phashes.len();
-    let mut group_id = vec![usize::MAX; n];
-    let mut next_group = 0usize;
-
-    for i in 0..n {
-        for j in (i+1)..n {
-            let dist = hamming_distance(phashes[i].2, phashes[j].2);
-            if dist <= threshold {
-                match (group_id[i], group_id[j]) {
-                    (usize::MAX, usize::MAX) => {
-                        group_id[i] = next_group;
-                        group_id[j] = next_group;
-                        next_group += 1;
-                    }
-                    (g, usize::MAX) => { group_id[j] = g; }
-                    (usize::MAX, g) => { group_id[i] = g; }
-                    (gi, gj) if gi != gj => {
-                        // Merge groups: relabel all gj -> gi
-                        for k in 0..n { if group_id[k] == gj { group_id[k] = gi; } }
-                    }
-                    _ => {}
-                }
-            }
-        }
-    }
+    let mut groups: Vec<DuplicateGroup> = by_hash.into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .map(|(hash, files)| {
+            let size = files[0].size;
+            let category = files[0].category.clone();
+            let wasted_bytes = size * (files.len() as u64 - 1);
+            DuplicateGroup { hash, size, category, files, wasted_bytes }
+        })
+        .collect();
 
-    // Collect groups
-    let mut groups: std::collections::HashMap<usize, Vec<usize>> = std::collections::HashMap::new();
-    for (i, &gid) in group_id.iter().enumerate() {
-        if gid != usize::MAX { groups.entry(gid).or_default().push(i); }
+    match sort_by.as_deref().unwrap_or("wasted") {
+        "count" => groups.sort_by_key(|g| std::cmp::Reverse(g.files.len())),
+        "size"  => groups.sort_by_key(|g| std::cmp::Reverse(g.size)),
+        _       => groups.sort_by_key(|g| std::cmp::Reverse(g.wasted_bytes)),
     }
+    Ok(groups)
+}
+
+// ── CATEGORIES ─────────────────────────────────────────────────
+// Changing the rules re-categorises every indexed location straight away
 
-    // Build result: pick "best" = largest file size as representative
+// Content type comes from the stat cache, so nothing is re-read from disk here
+fn recategorize_all(state: &AppState, rules: &CategoryRules, progress: &JobProgress) -> Result<usize, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
-    let mut result = Vec::new();
-    for (_, indices) in groups {
-        if indices.len() < 2 { continue; }
-
-        // Pick best by size
-        let best_idx = indices.iter().copied().max_by_key(|&i| {
-            let path = &phashes[i].1;
-            fs::metadata(path).map(|m| m.len()).unwrap_or(0)
-        }).unwrap();
+    let cache = stat_cache_tree(&db)?;
+    let mut changed = Vec::new();
+    for (hash, mut meta) in all_locations(&db) {
+        if progress.is_cancelled() { break; }
+        let detected = detected_type(&cache, &meta);
+        let category = rules.classify(&meta.path, meta.size, &detected);
+        progress.processed(meta.size, false);
+        if category != meta.category {
+            meta.category = category;
+            changed.push((hash, meta));
+        }
+    }
+    put_locations(&db, &changed)?;
+    db.flush().map_err(|e| e.to_string())?;
+    Ok(changed.len())
+}
 
-        let best_hash = phashes[best_idx].0.clone();
-        let others: Vec<String> = indices.iter()
-            .filter(|&&i| i != best_idx)
-            .map(|&i| phashes[i].0.clone())
-            .collect();
-
-        // Compute average similarity pct across all pairs
-        let max_ham = 64u32;
-        let avg_dist = if indices.len() < 2 { 0 } else {
-            let mut total = 0u32;
-            let mut pairs = 0u32;
-            for &a in &indices {
-                for &b in &indices {
-                    if a < b {
-                        total += hamming_distance(phashes[a].2, phashes[b].2);
-                        pairs += 1;
-                    }
-                }
-            }
-            if pairs > 0 { total / pairs } else { 0 }
-        };
-        let similarity_pct = ((max_ham - avg_dist) * 100 / max_ham) as u32;
+#[tauri::command]
+fn get_category_rules(state: State<'_, AppState>) -> Result<Vec<CategoryRule>, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    Ok(category_rule_list(&vdb))
+}
 
-        result.push((best_hash, others, similarity_pct));
+#[tauri::command]
+fn set_category_rules(rules: Vec<CategoryRule>, app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+    let compiled = Arc::new(CategoryRules::compile(&rules)?);
+    {
+        let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+        let encoded = serde_json::to_string(&rules).map_err(|e| e.to_string())?;
+        vdb.insert(b"settings::category_rules", encoded.as_bytes()).map_err(|e| e.to_string())?;
+        vdb.flush().map_err(|e| e.to_string())?;
     }
+    *state.category_rules.lock().map_err(|e| e.to_string())? = Some(Arc::clone(&compiled));
+    let changed = state.jobs.run_inline(&app, None, "recategorize", "Re-categorise index".to_string(), |progress| recategorize_all(&state, &compiled, progress))?;
+    Ok(format!("Rules saved. {} files re-categorised.", changed))
+}
 
-    // Sort by group size descending
-    result.sort_by(|a, b| b.1.len().cmp(&a.1.len()));
-    Ok(result)
+#[tauri::command]
+fn list_categories(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    Ok(category_rules(&state)?.categories())
 }
 
-// ── SCAN + AUTO SNAPSHOT ───────────────────────────────────────
+// ── TAGS + ANNOTATIONS ─────────────────────────────────────────
 
 #[tauri::command]
-async fn start_auto_scan(
-    folder_path: String,
-    snapshot_name: String,
-    state: State<'_, AppState>,
-) -> Result<String, String> {
-    let timestamp = now_ts();
-    let mut count = 0usize;
+fn get_annotation(hash: String, state: State<'_, AppState>) -> Result<Annotation, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    annotations::get(&vdb, &hash)
+}
 
-    let previous: Vec<(String, FileMeta)> = {
-        let db = state.db.lock().map_err(|e| e.to_string())?;
-        let norm = folder_path.replace('\\', "/").to_lowercase();
-        db.iter().filter_map(|i| i.ok()).filter_map(|(k, v)| {
-            let hash = String::from_utf8_lossy(&k).to_string();
-            serde_json::from_slice::<FileMeta>(&v).ok().map(|m| (hash, m))
-        }).filter(|(_, m)| m.path.replace('\\', "/").to_lowercase().starts_with(&norm)).collect()
-    };
+#[tauri::command]
+fn add_tags(hash: String, tags: Vec<String>, state: State<'_, AppState>) -> Result<Annotation, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    annotations::update(&vdb, &hash, |a| {
+        for tag in tags.iter().filter_map(|t| annotations::normalize_tag(t)) {
+            if !a.tags.contains(&tag) { a.tags.push(tag); }
+        }
+        a.tags.sort();
+    })
+}
 
-    let mut scanned = std::collections::HashSet::new();
+#[tauri::command]
+fn remove_tags(hash: String, tags: Vec<String>, state: State<'_, AppState>) -> Result<Annotation, String> {
+    let remove: Vec<String> = tags.iter().filter_map(|t| annotations::normalize_tag(t)).collect();
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    annotations::update(&vdb, &hash, |a| a.tags.retain(|t| !remove.contains(t)))
+}
 
-    for entry in WalkDir::new(&folder_path).into_iter().filter_map(|e| e.ok()) {
-        if !entry.file_type().is_file() { continue; }
-        let path = entry.path().to_string_lossy().to_string();
-        let metadata = fs::metadata(entry.path()).ok();
-        let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-        let modified = format!("{:?}", metadata.as_ref().and_then(|m| m.modified().ok()).unwrap_or(SystemTime::now()));
-        let category = get_category(&path);
-        if let Ok(hash) = calculate_hash(&path) {
-            scanned.insert(hash.clone());
-let meta = FileMeta { 
-    path: path.to_string(), // Convert &str to String
-    size, 
-    modified, 
-    category 
-};
-            let db = state.db.lock().map_err(|e| e.to_string())?;
-            db.insert(hash.as_bytes(), serde_json::to_string(&meta).unwrap().as_bytes()).unwrap();
-            count += 1;
+#[tauri::command]
+fn set_rating(hash: String, rating: Option<u8>, state: State<'_, AppState>) -> Result<Annotation, String> {
+    if rating.is_some_and(|r| !(1..=5).contains(&r)) { return Err("Rating must be between 1 and 5".to_string()); }
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    annotations::update(&vdb, &hash, |a| a.rating = rating)
+}
+
+#[tauri::command]
+fn set_color_label(hash: String, color: Option<String>, state: State<'_, AppState>) -> Result<Annotation, String> {
+    if let Some(c) = &color {
+        if !annotations::COLOR_LABELS.contains(&c.as_str()) { return Err(format!("Unknown colour label: {}", c)); }
+    }
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    annotations::update(&vdb, &hash, |a| a.color = color)
+}
+
+#[tauri::command]
+fn set_note(hash: String, note: String, state: State<'_, AppState>) -> Result<Annotation, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    annotations::update(&vdb, &hash, |a| a.note = note.trim().to_string())
+}
+
+#[tauri::command]
+fn list_tags(state: State<'_, AppState>) -> Result<Vec<TagCount>, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    annotations::tag_counts(&vdb)
+}
+
+// Every indexed location whose content carries `tag`
+#[tauri::command]
+fn files_with_tag(tag: String, state: State<'_, AppState>) -> Result<Vec<(String, FileMeta)>, String> {
+    let tag = annotations::normalize_tag(&tag).ok_or("Empty tag")?;
+    let hashes = { let vdb = state.version_db.lock().map_err(|e| e.to_string())?; annotations::hashes_with_tag(&vdb, &tag)? };
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    Ok(hashes.into_iter()
+        .flat_map(|hash| hash_locations(&db, &hash).into_iter().map(move |m| (hash.clone(), m)))
+        .collect())
+}
+
+// ── SEARCH ─────────────────────────────────────────────────────
+// Query syntax lives in search.rs; results are filtered, sorted and paged here
+// so the frontend only ever receives one page.
+
+const SEARCH_PAGE_DEFAULT: usize = 200;
+const SEARCH_PAGE_MAX: usize = 1000;
+
+fn search_index(state: &AppState, query: &search::Query) -> Result<Vec<SearchHit>, String> {
+    let mut tagged: std::collections::HashMap<String, std::collections::HashSet<String>> = std::collections::HashMap::new();
+    {
+        let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+        for tag in query.tags() {
+            let hashes = annotations::hashes_with_tag(&vdb, &tag)?.into_iter().collect();
+            tagged.insert(tag, hashes);
+        }
+    }
+    // Category terms are lower-cased; the index is keyed by the category's real name
+    let category = match query.category() {
+        Some(c) => category_rules(state)?.categories().into_iter().find(|k| k.to_lowercase() == c),
+        None => None,
+    };
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let mut hits = Vec::new();
+    let mut consider = |hash: String, meta: FileMeta| {
+        if query.matches(&hash, &meta, |t| tagged.get(t)) { hits.push(SearchHit { hash, meta }); }
+    };
+    // A tag, category or path term narrows the candidates, so no full scan is needed
+    if let Some(set) = query.required_tags().iter().filter_map(|t| tagged.get(t)).min_by_key(|s| s.len()) {
+        for hash in set {
+            for meta in hash_locations(&db, hash) { consider(hash.clone(), meta); }
+        }
+    } else if let Some(category) = category {
+        for (hash, meta) in locations_in_category(&db, &category) { consider(hash, meta); }
+    } else if let Some(prefix) = query.path_prefix() {
+        for (hash, meta) in resolve(&db, Indexes::open(&db)?.with_prefix(&prefix)) { consider(hash, meta); }
+    } else {
+        for (hash, meta) in all_locations(&db) { consider(hash, meta); }
+    }
+    Ok(hits)
+}
+
+#[tauri::command]
+fn search_files(
+    query: String,
+    sort_by: Option<String>,
+    descending: Option<bool>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<SearchPage, String> {
+    let parsed = search::Query::parse(&query)?;
+    let mut hits = search_index(&state, &parsed)?;
+    search::sort_hits(&mut hits, sort_by.as_deref().unwrap_or("path"), descending.unwrap_or(false));
+    let total = hits.len();
+    let offset = offset.unwrap_or(0).min(total);
+    let limit = limit.unwrap_or(SEARCH_PAGE_DEFAULT).clamp(1, SEARCH_PAGE_MAX);
+    let items = hits.into_iter().skip(offset).take(limit).collect();
+    Ok(SearchPage { total, offset, items })
+}
+
+// ── CONTENT SEARCH ─────────────────────────────────────────────
+// Full-text search over extracted document text (fulltext.rs). Words are ANDed,
+// "quoted phrases" must appear as written; results are ranked by BM25.
+
+#[tauri::command]
+fn search_content(query: String, offset: Option<usize>, limit: Option<usize>, state: State<'_, AppState>) -> Result<ContentPage, String> {
+    // Ranking only reads, so it runs on a handle to the db rather than under its lock
+    let db = state.db.lock().map_err(|e| e.to_string())?.clone();
+    let content = ContentIndex::open(&db)?;
+    let limit = limit.unwrap_or(SEARCH_PAGE_DEFAULT).clamp(1, SEARCH_PAGE_MAX);
+    content.search(&query, offset.unwrap_or(0), limit, |hash| hash_locations(&db, hash).into_iter().next())
+}
+
+// ── INTEGRITY ──────────────────────────────────────────────────
+// Scrubs re-hash a folder's files and keep a dated report of what no longer
+// matches its stored hash (integrity.rs).
+
+fn scrub_folder(state: &AppState, progress: &JobProgress, folder_path: &str) -> Result<IntegrityReport, String> {
+    let timestamp = now_ts();
+    let files = { let db = state.db.lock().map_err(|e| e.to_string())?; locations_under(&db, folder_path) };
+    for (_, meta) in &files { progress.discovered(meta.size); }
+    progress.discovery_done();
+
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let outcomes: Vec<(usize, Outcome)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..scan::default_threads()).map(|_| scope.spawn(|| {
+            let mut done = Vec::new();
+            while !progress.is_cancelled() {
+                let i = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let Some((hash, meta)) = files.get(i) else { break };
+                progress.working_on(&meta.path);
+                let outcome = integrity::check(hash, meta);
+                progress.processed(meta.size, matches!(outcome, Outcome::Verified | Outcome::Corrupted(_)));
+                done.push((i, outcome));
+            }
+            done
+        })).collect();
+        workers.into_iter().flat_map(|w| w.join().unwrap_or_default()).collect()
+    });
+    if progress.is_cancelled() {
+        return Err(format!("Verification cancelled after {} of {} files. No report was saved.", outcomes.len(), files.len()));
+    }
+
+    let blobs_at = blob_dir(state);
+    let issue = |i: usize, actual_hash: Option<String>, error: Option<String>| {
+        let (hash, meta) = &files[i];
+        IntegrityIssue { path: meta.path.clone(), expected_hash: hash.clone(), actual_hash, error, recoverable: blobs::contains(&blobs_at, hash) }
+    };
+    let mut report = IntegrityReport { folder_path: folder_path.to_string(), timestamp, finished_at: 0, verified: 0, changed: 0, corrupted: Vec::new(), missing: Vec::new(), unreadable: Vec::new() };
+    for (i, outcome) in outcomes {
+        match outcome {
+            Outcome::Verified => report.verified += 1,
+            Outcome::Changed => report.changed += 1,
+            Outcome::Corrupted(actual) => report.corrupted.push(issue(i, Some(actual), None)),
+            Outcome::Missing => report.missing.push(issue(i, None, None)),
+            Outcome::Unreadable(e) => report.unreadable.push(issue(i, None, Some(e))),
+        }
+    }
+    for list in [&mut report.corrupted, &mut report.missing, &mut report.unreadable] { list.sort_by(|a, b| a.path.cmp(&b.path)); }
+    report.finished_at = now_ts();
+    { let vdb = state.version_db.lock().map_err(|e| e.to_string())?; integrity::save(&vdb, &report)?; }
+
+    progress.log(format!("{} verified, {} corrupted, {} missing, {} unreadable, {} changed since the last scan",
+        report.verified, report.corrupted.len(), report.missing.len(), report.unreadable.len(), report.changed));
+    Ok(report)
+}
+
+#[tauri::command]
+fn verify_integrity(folder_path: String, app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+    let label = format!("Verify {}", folder_path);
+    state.jobs.enqueue(&app, "verify", label, Box::new(move |app, progress| {
+        let report = scrub_folder(&app.state::<AppState>(), progress, &folder_path)?;
+        serde_json::to_value(report).map_err(|e| e.to_string())
+    }))
+}
+
+#[tauri::command]
+fn get_integrity_reports(folder_path: Option<String>, state: State<'_, AppState>) -> Result<Vec<IntegrityReport>, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    Ok(integrity::list(&vdb, folder_path.as_deref()))
+}
+
+#[tauri::command]
+fn delete_integrity_report(timestamp: u64, folder_path: String, state: State<'_, AppState>) -> Result<bool, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    integrity::delete(&vdb, timestamp, &folder_path)
+}
+
+// ── TYPE MISMATCHES ────────────────────────────────────────────
+// Indexed files whose bytes disagree with their extension (a .jpg that is really a PDF).
+// Compared against the detected type, never the user-rule category, which may be anything.
+
+#[tauri::command]
+fn find_type_mismatches(state: State<'_, AppState>) -> Result<Vec<TypeMismatch>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let cache = stat_cache_tree(&db)?;
+    let mut report: Vec<TypeMismatch> = all_locations(&db).into_iter()
+        .filter_map(|(hash, m)| {
+            let detected = detected_type(&cache, &m);
+            filetype::is_mismatch(&m.path, &detected).then(|| TypeMismatch {
+                hash,
+                extension: filetype::extension_of(&m.path),
+                extension_category: filetype::extension_category(&m.path).to_string(),
+                path: m.path,
+                mime: detected.mime,
+                category: detected.category,
+            })
+        })
+        .collect();
+    report.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(report)
+}
+
+// ── SMART DEDUP — PERCEPTUAL HASHING ──────────────────────────
+// `algorithms` picks the hashes compared and each one's max Hamming distance
+// (0=identical, 64=totally different); without it pHash is used at `threshold`.
+// A pair matches when every listed hash is within its threshold, or any one
+// when `require_all` is false. `any_orientation` also matches rotated and mirrored copies.
+
+fn similar_criteria(threshold: u32, algorithms: Option<Vec<HashThreshold>>) -> Vec<HashThreshold> {
+    algorithms.filter(|a| !a.is_empty()).unwrap_or_else(|| vec![HashThreshold { algorithm: Algorithm::Perceptual, threshold }])
+}
+
+fn similar_label(criteria: &[HashThreshold], require_all: bool, any_orientation: bool) -> String {
+    let parts: Vec<String> = criteria.iter().map(|c| format!("{} ≤ {}", c.algorithm.label(), c.threshold)).collect();
+    let turned = if any_orientation { ", any orientation" } else { "" };
+    format!("Similar images ({}{})", parts.join(if require_all { " and " } else { " or " }), turned)
+}
+
+#[tauri::command]
+async fn find_similar_images(
+    threshold: u32,
+    algorithms: Option<Vec<HashThreshold>>,
+    require_all: Option<bool>,
+    any_orientation: Option<bool>,
+    job_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<SimilarGroup>, String> {
+    let criteria = similar_criteria(threshold, algorithms);
+    let (require_all, any_orientation) = (require_all.unwrap_or(true), any_orientation.unwrap_or(false));
+    let label = similar_label(&criteria, require_all, any_orientation);
+    state.jobs.run_inline(&app, job_id, "similar", label, |progress| similar_images(&state, progress, &criteria, require_all, any_orientation))
+}
+
+fn similar_images(state: &AppState, progress: &JobProgress, criteria: &[HashThreshold], require_all: bool, any_orientation: bool) -> Result<Vec<SimilarGroup>, String> {
+
+    // Identical copies share a BLAKE3 hash, so one existing location per hash is enough.
+    // Pictures are picked by detected type, whatever category a user rule gave them.
+    let images: Vec<(String, FileMeta)> = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let cache = stat_cache_tree(&db)?;
+        let mut by_hash: std::collections::BTreeMap<String, FileMeta> = std::collections::BTreeMap::new();
+        for (hash, m) in all_locations(&db) {
+            if by_hash.contains_key(&hash) || detected_type(&cache, &m).category != "image" { continue; }
+            if Path::new(&m.path).exists() {
+                by_hash.insert(hash, m);
+            }
+        }
+        by_hash.into_iter().collect()
+    };
+    if images.is_empty() { return Ok(vec![]); }
+
+    // Fingerprints come from indexing; only images indexed before that existed get decoded here
+    let index = { let db = state.db.lock().map_err(|e| e.to_string())?; ImageIndex::open(&db)? };
+    let missing: Vec<&(String, FileMeta)> = images.iter().filter(|(h, _)| !index.contains(h)).collect();
+    for (_, meta) in &missing { progress.discovered(meta.size); }
+    progress.discovery_done();
+    for chunk in missing.chunks(256) {
+        let files: Vec<(String, String)> = chunk.iter().map(|(h, m)| (h.clone(), m.path.clone())).collect();
+        index.index_files(&files, scan::default_threads(), progress);
+        // Whatever finished is kept, so a cancelled search still saves work for the next one
+        if progress.is_cancelled() { return Err("Search cancelled".to_string()); }
+        for (_, meta) in chunk { progress.processed(meta.size, true); }
+    }
+
+    let fingerprinted: Vec<(&String, &FileMeta, similar::ImageFingerprint)> = images.iter()
+        .filter_map(|(h, m)| index.get(h).map(|fp| (h, m, fp)))
+        .collect();
+    let hashes: Vec<Vec<[u64; 8]>> = criteria.iter()
+        .map(|c| fingerprinted.iter().map(|(_, _, fp)| fp.oriented.map(|h| h.get(c.algorithm))).collect())
+        .collect();
+    let thresholds: Vec<u32> = criteria.iter().map(|c| c.threshold).collect();
+
+    // Best = most pixels, then largest file: the likeliest original
+    let groups = similar::group(&hashes, &thresholds, require_all, any_orientation, scan::default_threads());
+    let mut result: Vec<SimilarGroup> = groups.iter().map(|group| {
+        let best = *group.members.iter().max_by_key(|&&i| {
+            let (_, meta, fp) = &fingerprinted[i];
+            (fp.width as u64 * fp.height as u64, meta.size)
+        }).unwrap();
+        let pairs: Vec<SimilarPair> = group.links(best).into_iter().map(|(member, matched_to, edge)| {
+            let hashes: Vec<HashMatch> = criteria.iter().zip(&edge.distances)
+                .map(|(c, &distance)| HashMatch { algorithm: c.algorithm, distance, matched: distance <= c.threshold })
+                .collect();
+            let mean = edge.distances.iter().sum::<u32>() as f64 / edge.distances.len() as f64;
+            let similarity_pct = ((64.0 - mean) * 100.0 / 64.0).round() as u32;
+            SimilarPair {
+                hash: fingerprinted[member].0.clone(),
+                matched_to: fingerprinted[matched_to].0.clone(),
+                transform: edge.transform_from(member),
+                hashes,
+                similarity_pct,
+            }
+        }).collect();
+        let similarity_pct = (pairs.iter().map(|p| p.similarity_pct).sum::<u32>() as f64 / pairs.len() as f64).round() as u32;
+        let others = pairs.iter().map(|p| p.hash.clone()).collect();
+        SimilarGroup { best: fingerprinted[best].0.clone(), others, similarity_pct, pairs }
+    }).collect();
+
+    // Sort by group size descending
+    result.sort_by(|a, b| b.others.len().cmp(&a.others.len()));
+    progress.log(format!("{} images compared ({} fingerprinted now), {} groups found", fingerprinted.len(), missing.len(), result.len()));
+    Ok(result)
+}
+
+// ── SCAN + AUTO SNAPSHOT ───────────────────────────────────────
+
+#[tauri::command]
+async fn start_auto_scan(
+    folder_path: String,
+    snapshot_name: String,
+    force_full: Option<bool>,
+    threads: Option<usize>,
+    job_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let opts = ScanOptions { threads: threads.unwrap_or_else(scan::default_threads), force_full: force_full.unwrap_or(false) };
+    let label = format!("Scan {} → '{}'", folder_path, snapshot_name);
+    state.jobs.run_inline(&app, job_id, "scan", label, |progress| scan_and_snapshot(&state, progress, folder_path, snapshot_name, &opts, false))
+}
+
+fn scan_and_snapshot(state: &AppState, progress: &JobProgress, folder_path: String, snapshot_name: String, opts: &ScanOptions, scheduled: bool) -> Result<String, String> {
+    let timestamp = now_ts();
+    let mut count = 0usize;
+
+    let (previous, cache): (Vec<(String, FileMeta)>, sled::Tree) = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        (locations_under(&db, &folder_path), stat_cache_tree(&db)?)
+    };
+
+    let mut scanned: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+    let mut manifest: Vec<ManifestEntry> = Vec::new();
+    let blobs_at = blob_dir(state);
+    let rules = ignore_rules(state)?;
+    let categories = category_rules(state)?;
+    let (content, images) = { let db = state.db.lock().map_err(|e| e.to_string())?; (ContentIndex::open(&db)?, ImageIndex::open(&db)?) };
+
+    // Text extraction rereads the file, so it runs on the hashing workers rather than here
+    let extract_text = |f: &scan::ScannedFile| {
+        if let Err(e) = content.index_file(&f.hash, &f.path, &f.file_type) { progress.log(e); }
+    };
+    let walked = scan::scan_folder(&folder_path, &cache, opts, &rules, progress, &extract_text, |files| {
+        let mut stats = sled::Batch::default();
+        let mut entries = Vec::with_capacity(files.len());
+        let mut detected = Vec::with_capacity(files.len());
+        let mut unchanged = Vec::with_capacity(files.len());
+        for f in files {
+            let stat = StatEntry { size: f.size, modified: f.modified.clone(), inode: f.inode, hash: f.hash.clone(), file_type: Some(f.file_type.clone()) };
+            let encoded = serde_json::to_string(&stat).map_err(|e| e.to_string())?;
+            // A cache hit only needs writing back when its file type was sniffed just now
+            if !f.cached || cache.get(f.path.as_bytes()).ok().flatten().is_none_or(|v| v != encoded.as_bytes()) {
+                stats.insert(f.path.as_bytes(), encoded.as_bytes());
+            }
+            unchanged.push(f.cached);
+            scanned.insert(f.path.clone(), f.hash.clone());
+            manifest.push(ManifestEntry { path: f.path.clone(), hash: f.hash.clone(), size: f.size, modified: f.modified.clone() });
+            let category = categories.classify(&f.path, f.size, &f.file_type);
+            entries.push((f.hash, FileMeta { path: f.path, size: f.size, modified: f.modified, category, mime: f.file_type.mime.clone() }));
+            detected.push(f.file_type);
         }
+        let pictures: Vec<(String, String)> = entries.iter().zip(&detected).filter(|(_, t)| t.category == "image").map(|((h, m), _)| (h.clone(), m.path.clone())).collect();
+        for e in images.index_files(&pictures, opts.threads, progress) { progress.log(e); }
+        cache.apply_batch(stats).map_err(|e| e.to_string())?;
+        count += entries.len();
+        // Unchanged files already indexed as they are cost one read each, not a rewrite of every index
+        let (changed, types): (Vec<(String, FileMeta)>, Vec<filetype::FileType>) = {
+            let db = state.db.lock().map_err(|e| e.to_string())?;
+            let paths = paths_tree(&db)?;
+            let (changed, types): (Vec<_>, Vec<_>) = entries.into_iter().zip(detected).zip(unchanged)
+                .filter(|(((hash, meta), _), cached)| !cached || !is_indexed_as(&db, &paths, hash, meta))
+                .map(|(entry, _)| entry)
+                .unzip();
+            put_locations(&db, &changed)?;
+            (changed, types)
+        };
+        let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+        for ((hash, meta), file_type) in changed.iter().zip(&types) { track_version(&vdb, &blobs_at, hash, meta, file_type)?; }
+        Ok(())
+    });
+    // Batches already written stay indexed; the deletion pass and snapshot need a complete walk
+    if progress.is_cancelled() {
+        return Err(format!("Scan cancelled after indexing {} files. No snapshot was saved.", count));
     }
+    walked?;
 
+    // Content that left one of its locations this scan may now be unreferenced
+    let released: std::collections::HashSet<&str> = previous.iter()
+        .filter(|(hash, meta)| scanned.get(&meta.path) != Some(hash))
+        .map(|(hash, _)| hash.as_str())
+        .collect();
+    let mut vanished = 0usize;
     for (hash, meta) in &previous {
-        if !scanned.contains(hash) && !Path::new(&meta.path).exists() {
-            let name = meta.path.split(|c| c == '/' || c == '\\').last().unwrap_or("").to_string();
-            let entry = DeletedEntry { hash: hash.clone(), path: meta.path.clone(), name, size: meta.size, category: meta.category.clone(), deleted_at: timestamp, snapshot_name: snapshot_name.clone() };
-            let del_key = format!("deleted::{}::{}", timestamp, hash);
-            { let vdb = state.version_db.lock().map_err(|e| e.to_string())?; vdb.insert(del_key.as_bytes(), serde_json::to_string(&entry).unwrap().as_bytes()).unwrap(); }
+        if scanned.contains_key(&meta.path) { continue; }
+        if !Path::new(&meta.path).exists() {
+            vanished += 1;
+            tombstone_location(state, hash, meta, timestamp, &snapshot_name)?;
+        } else if rules.is_ignored(Path::new(&folder_path), Path::new(&meta.path), false) {
+            // Still on disk, just newly ignored: drop it from the index without a tombstone
             let db = state.db.lock().map_err(|e| e.to_string())?;
-            db.remove(hash.as_bytes()).unwrap();
+            remove_location(&db, &meta.path)?;
         }
     }
 
-    let snap = SnapshotInfo { name: snapshot_name.clone(), timestamp, file_count: count, folder_path };
-    let snap_key = format!("snapshot::{}::{}", timestamp, snapshot_name);
+    {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let is_live = |hash: &str| db.scan_prefix(format!("{}::", hash).as_bytes()).next().is_some();
+        content.prune(released.iter().copied(), is_live)?;
+        images.prune(released.iter().copied(), is_live)?;
+    }
+    {
+        let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+        write_manifest(&vdb, timestamp, &snapshot_name, &manifest)?;
+        let snap = SnapshotInfo { name: snapshot_name.clone(), timestamp, file_count: count, folder_path: folder_path.clone(), scheduled };
+        let encoded = serde_json::to_string(&snap).map_err(|e| e.to_string())?;
+        vdb.insert(snapshot_key(timestamp, &snapshot_name).as_bytes(), encoded.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    // Scanned roots stay live from now on; a folder that cannot be watched is still indexed
+    if let Err(e) = register_watch_root(state, &folder_path) { progress.log(format!("Not watching {}: {}", folder_path, e)); }
+
+    let (processed, hashed, unreadable) = progress.counts();
+    progress.log(format!("{} files indexed, {} hashed, {} unreadable, {} gone since the last scan", count, hashed, unreadable, vanished));
+    Ok(format!("Indexed {} files ({} hashed, {} unchanged, {} unreadable). Snapshot '{}' saved.",
+        count, hashed, processed - hashed - unreadable, unreadable, snapshot_name))
+}
+
+#[tauri::command]
+fn cancel_scan(job_id: String, state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.jobs.cancel(&job_id))
+}
+
+// ── WATCHED FOLDERS ────────────────────────────────────────────
+// watch::{root} → WatchedFolder in version_db. Every scanned root is registered;
+// debounced disk changes under a root are applied to the index as they happen.
+
+fn watch_key(root: &str) -> String {
+    format!("watch::{}", root)
+}
+
+fn register_watch_root(state: &AppState, root: &str) -> Result<(), String> {
+    state.watcher.watch(root)?;
     let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
-    vdb.insert(snap_key.as_bytes(), serde_json::to_string(&snap).unwrap().as_bytes()).unwrap();
+    if vdb.contains_key(watch_key(root).as_bytes()).map_err(|e| e.to_string())? { return Ok(()); }
+    save_watch_root(&vdb, &WatchedFolder { path: root.to_string(), added_at: now_ts(), schedule: None, last_run: 0 })
+}
 
-    Ok(format!("Indexed {} files. Snapshot '{}' saved.", count, snapshot_name))
+fn save_watch_root(vdb: &Db, folder: &WatchedFolder) -> Result<(), String> {
+    let encoded = serde_json::to_string(folder).map_err(|e| e.to_string())?;
+    vdb.insert(watch_key(&folder.path).as_bytes(), encoded.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(())
 }
 
-// ── VAULT ──────────────────────────────────────────────────────
+fn saved_watch_roots(vdb: &Db) -> Vec<WatchedFolder> {
+    vdb.scan_prefix(b"watch::").filter_map(|r| r.ok())
+        .filter_map(|(_, v)| serde_json::from_slice(&v).ok())
+        .collect()
+}
+
+fn watch_root_paths(state: &AppState) -> Vec<String> {
+    state.version_db.lock().map(|vdb| saved_watch_roots(&vdb).into_iter().map(|f| f.path).collect()).unwrap_or_default()
+}
+
+// The watched root `path` lives under (the deepest one when roots nest); ignore rules match below it
+fn watch_root_of<'a>(roots: &'a [String], path: &str) -> Option<&'a Path> {
+    roots.iter().map(Path::new).filter(|r| Path::new(path).starts_with(r)).max_by_key(|r| r.components().count())
+}
+
+// Roots that no longer exist stay registered and are picked up again once they return
+fn rewatch_saved_roots(state: &AppState) {
+    let roots = match state.version_db.lock() { Ok(vdb) => saved_watch_roots(&vdb), Err(_) => return };
+    for root in roots {
+        if Path::new(&root.path).is_dir() { let _ = state.watcher.watch(&root.path); }
+    }
+}
+
+// Re-hashes only when the stat cache says the file changed
+fn reindex_if_changed(state: &AppState, path: &str) -> Result<(), String> {
+    let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
+    let modified = format!("{:?}", metadata.modified().unwrap_or(SystemTime::now()));
+    let unchanged = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let cached = scan::cached_stat(&stat_cache_tree(&db)?, path, metadata.len(), &modified, scan::inode_of(&metadata));
+        cached.is_some() && paths_tree(&db)?.contains_key(path.as_bytes()).map_err(|e| e.to_string())?
+    };
+    if !unchanged { index_single_path(path, state)?; }
+    Ok(())
+}
+
+// The location at `path` plus everything indexed below it
+fn locations_at_or_under(db: &Db, path: &str) -> Result<Vec<(String, FileMeta)>, String> {
+    let mut found = locations_under(db, &format!("{}/", path.trim_end_matches(['/', '\\'])));
+    if let Some(h) = paths_tree(db)?.get(path.as_bytes()).map_err(|e| e.to_string())? {
+        let hash = String::from_utf8_lossy(&h).to_string();
+        if let Ok(meta) = get_location(db, &hash, path) { found.push((hash, meta)); }
+    }
+    Ok(found)
+}
+
+// Applies one debounced change. Every path it may have added, moved or dropped goes
+// into `touched`; failures on single files inside a folder go into `errors` so the
+// rest of the folder is still applied.
+fn apply_fs_change(state: &AppState, rules: &IgnoreRules, roots: &[String], change: FsChange, timestamp: u64, touched: &mut std::collections::BTreeSet<String>, errors: &mut Vec<String>) -> Result<(), String> {
+    let own_data = state.data_dir.to_string_lossy().to_string();
+    match change {
+        FsChange::Renamed(from, to) => {
+            if to.starts_with(&own_data) { return Ok(()); }
+            let root = watch_root_of(roots, &to).unwrap_or(Path::new(""));
+            if rules.is_ignored(root, Path::new(&to), Path::new(&to).is_dir()) {
+                // Moved somewhere ignored: it left the vault's view without being deleted
+                let db = state.db.lock().map_err(|e| e.to_string())?;
+                for (_, meta) in locations_at_or_under(&db, &from)? {
+                    remove_location(&db, &meta.path)?;
+                    touched.insert(meta.path);
+                }
+                return Ok(());
+            }
+            if Path::new(&to).is_dir() {
+                for (old, new) in relocate_folder(state, &from, &to)? { touched.insert(old); touched.insert(new); }
+            } else {
+                touched.insert(from.clone());
+                touched.insert(to.clone());
+                let moved = { let db = state.db.lock().map_err(|e| e.to_string())?; move_location(&db, &from, &to)? };
+                if moved.is_some() {
+                    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+                    move_versions(&vdb, &from, &to)?;
+                }
+                if Path::new(&to).is_file() { reindex_if_changed(state, &to)?; }
+            }
+        }
+        FsChange::Changed(path) => {
+            let p = Path::new(&path);
+            let root = watch_root_of(roots, &path).unwrap_or(Path::new(""));
+            if path.starts_with(&own_data) || rules.is_ignored(root, p, p.is_dir()) { return Ok(()); }
+            if p.is_file() {
+                touched.insert(path.clone());
+                reindex_if_changed(state, &path)?;
+            } else if p.is_dir() {
+                // A folder moved in from outside the watched roots arrives as a single create
+                for entry in ignores::walk_under(root, p, rules) {
+                    if !entry.file_type().is_file() { continue; }
+                    let file = entry.path().to_string_lossy().to_string();
+                    if let Err(e) = reindex_if_changed(state, &file) { errors.push(format!("{}: {}", file, e)); }
+                    touched.insert(file);
+                }
+            }
+        }
+        FsChange::Removed(path) => {
+            if Path::new(&path).exists() { return Ok(()); }
+            let gone = { let db = state.db.lock().map_err(|e| e.to_string())?; locations_at_or_under(&db, &path)? };
+            for (hash, meta) in &gone {
+                match tombstone_location(state, hash, meta, timestamp, "watcher") {
+                    Ok(()) => { touched.insert(meta.path.clone()); }
+                    Err(e) => errors.push(format!("{}: {}", meta.path, e)),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Applies a batch change by change, so one failure never drops the rest of it.
+// The result lists where each touched path ended up for the "index-changed" event.
+fn apply_fs_changes(state: &AppState, changes: Vec<FsChange>) -> IndexChange {
+    let timestamp = now_ts();
+    let mut touched = std::collections::BTreeSet::new();
+    let mut errors = Vec::new();
+    let roots = watch_root_paths(state);
+    match ignore_rules(state) {
+        Ok(rules) => for change in changes {
+            let subject = match &change { FsChange::Changed(p) | FsChange::Removed(p) | FsChange::Renamed(_, p) => p.clone() };
+            if let Err(e) = apply_fs_change(state, &rules, &roots, change, timestamp, &mut touched, &mut errors) {
+                errors.push(format!("{}: {}", subject, e));
+            }
+        },
+        Err(e) => errors.push(e),
+    }
+    let mut report = IndexChange { updated: Vec::new(), removed: Vec::new(), errors };
+    let db = match state.db.lock() { Ok(db) => db, Err(e) => { report.errors.push(e.to_string()); return report; } };
+    let paths = match paths_tree(&db) { Ok(paths) => paths, Err(e) => { report.errors.push(e); return report; } };
+    for path in touched {
+        let indexed = paths.get(path.as_bytes()).ok().flatten()
+            .map(|h| String::from_utf8_lossy(&h).to_string())
+            .and_then(|hash| get_location(&db, &hash, &path).ok().map(|meta| SearchHit { hash, meta }));
+        match indexed {
+            Some(hit) => report.updated.push(hit),
+            None => report.removed.push(path),
+        }
+    }
+    report
+}
+
+#[tauri::command]
+fn get_watched_folders(state: State<'_, AppState>) -> Result<Vec<WatchedFolder>, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    Ok(saved_watch_roots(&vdb))
+}
 
 #[tauri::command]
-fn get_all_stored_files(state: State<'_, AppState>) -> Result<Vec<(String, String)>, String> {
+fn watch_folder(folder_path: String, state: State<'_, AppState>) -> Result<(), String> {
+    if !Path::new(&folder_path).is_dir() { return Err(format!("Not a folder: {}", folder_path)); }
+    register_watch_root(&state, &folder_path)
+}
+
+#[tauri::command]
+fn unwatch_folder(folder_path: String, state: State<'_, AppState>) -> Result<(), String> {
+    let _ = state.watcher.unwatch(&folder_path);
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    vdb.remove(watch_key(&folder_path).as_bytes()).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// ── SNAPSHOT SCHEDULES + RETENTION ─────────────────────────────
+// Only snapshots taken by the scheduler are ever pruned; manual ones stay until deleted.
+
+fn retention_policy(vdb: &Db) -> RetentionPolicy {
+    vdb.get(b"settings::retention").ok().flatten()
+        .and_then(|v| serde_json::from_slice(&v).ok())
+        .unwrap_or_default()
+}
+
+// Every hash a remaining manifest, tombstone or version chain may still want a blob for
+fn referenced_blob_hashes(vdb: &Db) -> std::collections::HashSet<String> {
+    let mut referenced = std::collections::HashSet::new();
+    for (_, v) in vdb.scan_prefix(b"snapshot::").filter_map(|r| r.ok()) {
+        let Ok(snap) = serde_json::from_slice::<SnapshotInfo>(&v) else { continue };
+        if let Ok(entries) = read_manifest(vdb, snap.timestamp, &snap.name) {
+            referenced.extend(entries.into_iter().map(|e| e.hash));
+        }
+    }
+    for (_, v) in vdb.scan_prefix(b"deleted::").filter_map(|r| r.ok()) {
+        if let Ok(entry) = serde_json::from_slice::<DeletedEntry>(&v) {
+            if entry.blob_stored { referenced.insert(entry.hash); }
+        }
+    }
+    for (_, v) in vdb.scan_prefix(b"version::").filter_map(|r| r.ok()) {
+        let chain: Vec<FileVersion> = serde_json::from_slice(&v).unwrap_or_default();
+        referenced.extend(chain.into_iter().filter(|f| f.blob_stored).map(|f| f.hash));
+    }
+    referenced
+}
+
+// Deletes every stored blob nothing references any more, including ones whose tombstones
+// were cleared. Must run with version_db held: that is the lock every blob is stored under.
+fn collect_blob_garbage(vdb: &Db, blob_dir: &Path) -> Result<usize, String> {
+    let referenced = referenced_blob_hashes(vdb);
+    let mut freed = 0;
+    for hash in blobs::list(blob_dir) {
+        if !referenced.contains(&hash) && blobs::remove(blob_dir, &hash)? { freed += 1; }
+    }
+    Ok(freed)
+}
+
+// Returns (snapshots removed, blobs freed)
+fn prune_scheduled_snapshots(state: &AppState, folder_path: &str) -> Result<(usize, usize), String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    let scheduled: Vec<SnapshotInfo> = vdb.scan_prefix(b"snapshot::").filter_map(|r| r.ok())
+        .filter_map(|(_, v)| serde_json::from_slice::<SnapshotInfo>(&v).ok())
+        .filter(|s| s.scheduled && s.folder_path == folder_path)
+        .collect();
+    let timestamps: Vec<u64> = scheduled.iter().map(|s| s.timestamp).collect();
+    let keep = schedule::snapshots_to_keep(&timestamps, &retention_policy(&vdb));
+
+    let mut removed = 0;
+    for snap in scheduled.iter().filter(|s| !keep.contains(&s.timestamp)) {
+        remove_snapshot(&vdb, snap.timestamp, &snap.name)?;
+        removed += 1;
+    }
+    if removed == 0 { return Ok((0, 0)); }
+
+    let freed = collect_blob_garbage(&vdb, &blob_dir(state))?;
+    vdb.flush().map_err(|e| e.to_string())?;
+    Ok((removed, freed))
+}
+
+// Scheduler tick: queues a scan for every watched folder whose interval has elapsed
+fn run_due_schedules(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let now = now_ts();
+    let due: Vec<(String, String)> = {
+        let Ok(vdb) = state.version_db.lock() else { return };
+        let mut due = Vec::new();
+        for mut folder in saved_watch_roots(&vdb) {
+            let Some(schedule) = folder.schedule.clone() else { continue };
+            let Some(interval) = schedule::interval_secs(&schedule) else { continue };
+            if now < folder.last_run + interval || !Path::new(&folder.path).is_dir() { continue; }
+            // Stamped before the scan runs so a slow scan is never queued twice
+            folder.last_run = now;
+            if save_watch_root(&vdb, &folder).is_ok() { due.push((folder.path, schedule)); }
+        }
+        due
+    };
+    for (folder_path, schedule) in due {
+        let label = format!("Scheduled {} snapshot of {}", schedule, folder_path);
+        let _ = state.jobs.enqueue(app, "scan", label, Box::new(move |app, progress| {
+            let state = app.state::<AppState>();
+            let opts = ScanOptions { threads: scan::default_threads(), force_full: false };
+            let message = scan_and_snapshot(&state, progress, folder_path.clone(), format!("auto-{}", schedule), &opts, true)?;
+            let (removed, freed) = prune_scheduled_snapshots(&state, &folder_path)?;
+            progress.log(format!("Retention removed {} snapshots and {} blobs", removed, freed));
+            Ok(serde_json::Value::from(message))
+        }));
+    }
+}
+
+#[tauri::command]
+fn set_watch_schedule(folder_path: String, schedule: Option<String>, state: State<'_, AppState>) -> Result<(), String> {
+    if let Some(s) = &schedule {
+        if schedule::interval_secs(s).is_none() { return Err(format!("Unknown schedule: {}", s)); }
+    }
+    if !Path::new(&folder_path).is_dir() { return Err(format!("Not a folder: {}", folder_path)); }
+    register_watch_root(&state, &folder_path)?;
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    let v = vdb.get(watch_key(&folder_path).as_bytes()).map_err(|e| e.to_string())?.ok_or("Folder is not watched")?;
+    let mut folder: WatchedFolder = serde_json::from_slice(&v).map_err(|e| e.to_string())?;
+    folder.schedule = schedule;
+    save_watch_root(&vdb, &folder)
+}
+
+#[tauri::command]
+fn get_retention_policy(state: State<'_, AppState>) -> Result<RetentionPolicy, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    Ok(retention_policy(&vdb))
+}
+
+#[tauri::command]
+fn set_retention_policy(policy: RetentionPolicy, state: State<'_, AppState>) -> Result<(), String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    let encoded = serde_json::to_string(&policy).map_err(|e| e.to_string())?;
+    vdb.insert(b"settings::retention", encoded.as_bytes()).map_err(|e| e.to_string())?;
+    vdb.flush().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+fn prune_snapshots(folder_path: String, state: State<'_, AppState>) -> Result<String, String> {
+    let (removed, freed) = prune_scheduled_snapshots(&state, &folder_path)?;
+    Ok(format!("Removed {} scheduled snapshots, freed {} stored copies", removed, freed))
+}
+
+// ── BACKGROUND JOBS ────────────────────────────────────────────
+// queue_* commands return a job id straight away; poll get_job or listen for "job-updated"
+
+#[tauri::command]
+fn queue_scan(folder_path: String, snapshot_name: String, force_full: Option<bool>, threads: Option<usize>, app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+    let opts = ScanOptions { threads: threads.unwrap_or_else(scan::default_threads), force_full: force_full.unwrap_or(false) };
+    let label = format!("Scan {} → '{}'", folder_path, snapshot_name);
+    state.jobs.enqueue(&app, "scan", label, Box::new(move |app, progress| {
+        let state = app.state::<AppState>();
+        scan_and_snapshot(&state, progress, folder_path, snapshot_name, &opts, false).map(serde_json::Value::from)
+    }))
+}
+
+#[tauri::command]
+fn queue_similar_images(
+    threshold: u32,
+    algorithms: Option<Vec<HashThreshold>>,
+    require_all: Option<bool>,
+    any_orientation: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let criteria = similar_criteria(threshold, algorithms);
+    let (require_all, any_orientation) = (require_all.unwrap_or(true), any_orientation.unwrap_or(false));
+    let label = similar_label(&criteria, require_all, any_orientation);
+    state.jobs.enqueue(&app, "similar", label, Box::new(move |app, progress| {
+        let state = app.state::<AppState>();
+        let groups = similar_images(&state, progress, &criteria, require_all, any_orientation)?;
+        serde_json::to_value(groups).map_err(|e| e.to_string())
+    }))
+}
+
+#[tauri::command]
+fn queue_compress(paths: Vec<String>, output_path: String, app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+    let label = format!("Compress to {}", output_path);
+    state.jobs.enqueue(&app, "zip", label, Box::new(move |app, progress| {
+        let rules = ignore_rules(&app.state::<AppState>())?;
+        write_zip(&paths, &output_path, &rules, progress).map(serde_json::Value::from)
+    }))
+}
+
+#[tauri::command]
+fn queue_move_folder(old_path: String, destination_parent: String, app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+    let label = format!("Move {} to {}", old_path, destination_parent);
+    state.jobs.enqueue(&app, "move", label, Box::new(move |app, _| {
+        let state = app.state::<AppState>();
+        relocate_folder_on_disk(&state, &old_path, &destination_parent).map(serde_json::Value::from)
+    }))
+}
+
+#[tauri::command]
+fn list_jobs(state: State<'_, AppState>) -> Result<Vec<JobSummary>, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    Ok(state.jobs.list(&vdb))
+}
+
+#[tauri::command]
+fn get_job(job_id: String, state: State<'_, AppState>) -> Result<JobSummary, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    state.jobs.get(&vdb, &job_id).ok_or_else(|| format!("No job {}", job_id))
+}
+
+#[tauri::command]
+fn cancel_job(job_id: String, state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.jobs.cancel(&job_id))
+}
+
+#[tauri::command]
+fn clear_job_history(state: State<'_, AppState>) -> Result<(), String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    state.jobs.clear_finished(&vdb)
+}
+
+// ── VAULT ──────────────────────────────────────────────────────
+// Listings walk the path/sort indexes from a cursor, so each page costs O(limit)
+// however large the vault is. sort_by: "path" (default), "name", "size", "modified", "hash"
+
+const LIST_PAGE_DEFAULT: usize = 500;
+const LIST_PAGE_MAX: usize = 5000;
+const STREAM_CHUNK: usize = 2000;
+
+fn list_page(state: &AppState, sort_by: &str, after: Option<&str>, limit: usize, descending: bool) -> Result<FilePage, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
-    Ok(db.iter().filter_map(|i| i.ok()).map(|(k, v)| (
-        String::from_utf8_lossy(&k).to_string(),
-        String::from_utf8_lossy(&v).to_string(),
-    )).collect())
+    let page = Indexes::open(&db)?.page(&db, sort_by, after, limit, descending)?;
+    let items = resolve(&db, page.entries).into_iter().map(|(hash, meta)| SearchHit { hash, meta }).collect();
+    Ok(FilePage { items, next: page.next })
+}
+
+#[tauri::command]
+fn list_stored_files(
+    sort_by: Option<String>,
+    after: Option<String>,
+    limit: Option<usize>,
+    descending: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<FilePage, String> {
+    let limit = limit.unwrap_or(LIST_PAGE_DEFAULT).clamp(1, LIST_PAGE_MAX);
+    list_page(&state, sort_by.as_deref().unwrap_or("path"), after.as_deref(), limit, descending.unwrap_or(false))
+}
+
+// Sends the whole vault down `on_chunk` a page at a time; the db lock is released
+// between chunks so the watcher and other commands keep running. Returns the count sent.
+#[tauri::command]
+async fn stream_stored_files(
+    sort_by: Option<String>,
+    descending: Option<bool>,
+    on_chunk: tauri::ipc::Channel<Vec<SearchHit>>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let sort_by = sort_by.unwrap_or_else(|| "path".to_string());
+    let mut after: Option<String> = None;
+    let mut sent = 0usize;
+    loop {
+        let page = list_page(&state, &sort_by, after.as_deref(), STREAM_CHUNK, descending.unwrap_or(false))?;
+        sent += page.items.len();
+        on_chunk.send(page.items).map_err(|e| e.to_string())?;
+        match page.next {
+            Some(next) => after = Some(next),
+            None => return Ok(sent),
+        }
+    }
 }
 
 #[tauri::command]
 fn clear_vault(state: State<'_, AppState>) -> Result<(), String> {
-    state.db.lock().map_err(|e| e.to_string())?.clear().map_err(|e| e.to_string())
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    paths_tree(&db)?.clear().map_err(|e| e.to_string())?;
+    stat_cache_tree(&db)?.clear().map_err(|e| e.to_string())?;
+    Indexes::open(&db)?.clear()?;
+    ContentIndex::open(&db)?.clear()?;
+    ImageIndex::open(&db)?.clear()?;
+    db.clear().map_err(|e| e.to_string())
 }
 
 // ── HISTORY ────────────────────────────────────────────────────
@@ -608,9 +1812,95 @@ fn clear_deleted_history(state: State<'_, AppState>) -> Result<(), String> {
     let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
     let keys: Vec<_> = vdb.scan_prefix(b"deleted::").filter_map(|i| i.ok().map(|(k, _)| k)).collect();
     for key in keys { vdb.remove(key).map_err(|e| e.to_string())?; }
+    // Copies kept only for those tombstones go with them
+    collect_blob_garbage(&vdb, &blob_dir(&state))?;
     Ok(())
 }
 
+#[tauri::command]
+fn restore_deleted(hash: String, target_path: String, state: State<'_, AppState>) -> Result<String, String> {
+    if Path::new(&target_path).exists() { return Err(format!("Target already exists: {}", target_path)); }
+    blobs::restore(&blob_dir(&state), &hash, Path::new(&target_path))?;
+    let indexed = index_single_path(&target_path, &state)?;
+    state.db.lock().map_err(|e| e.to_string())?.flush().map_err(|e| e.to_string())?;
+    Ok(format!("Restored: {} ({})", file_name_of(&target_path), &indexed[..12]))
+}
+
+#[tauri::command]
+fn get_ignore_patterns(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    Ok(ignore_patterns(&vdb))
+}
+
+#[tauri::command]
+fn set_ignore_patterns(patterns: Vec<String>, state: State<'_, AppState>) -> Result<(), String> {
+    IgnoreRules::new(&patterns)?;
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    let encoded = serde_json::to_string(&patterns).map_err(|e| e.to_string())?;
+    vdb.insert(b"settings::ignore", encoded.as_bytes()).map_err(|e| e.to_string())?;
+    vdb.flush().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_blob_store_enabled(state: State<'_, AppState>) -> Result<bool, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    Ok(blob_store_enabled(&vdb))
+}
+
+#[tauri::command]
+fn set_blob_store_enabled(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    vdb.insert(b"settings::blob_store", if enabled { &b"true"[..] } else { &b"false"[..] }).map_err(|e| e.to_string())?;
+    vdb.flush().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// ── VERSIONS ──────────────────────────────────────────────────
+
+#[tauri::command]
+fn get_file_versions(path: String, state: State<'_, AppState>) -> Result<Vec<FileVersion>, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    let mut chain = read_versions(&vdb, &path);
+    chain.reverse();
+    // blob_stored tells the UI which versions restore_version can bring back
+    let blobs_at = blob_dir(&state);
+    for version in &mut chain { version.blob_stored = version.blob_stored && blobs::contains(&blobs_at, &version.hash); }
+    Ok(chain)
+}
+
+#[tauri::command]
+fn restore_version(path: String, hash: String, state: State<'_, AppState>) -> Result<String, String> {
+    let target = {
+        let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+        read_versions(&vdb, &path).into_iter().find(|v| v.hash == hash).ok_or("Version not found")?
+    };
+    if !target.blob_stored { return Err("No stored copy of this version".to_string()); }
+
+    // Whatever is on disk now becomes a version of its own so the restore can be undone
+    if Path::new(&path).is_file() {
+        let current = calculate_hash(&path)?;
+        if current != hash {
+            let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+            let stored = blobs::store(&blob_dir(&state), Path::new(&path))?;
+            let metadata = fs::metadata(&path).map_err(|e| e.to_string())?;
+            let mut chain = read_versions(&vdb, &path);
+            if chain.last().map(|v| v.hash != stored).unwrap_or(true) {
+                let modified = format!("{:?}", metadata.modified().unwrap_or(SystemTime::now()));
+                chain.push(FileVersion { hash: stored, size: metadata.len(), modified, seen_at: now_ts(), blob_stored: true });
+            } else if let Some(last) = chain.last_mut() {
+                last.blob_stored = true;
+            }
+            write_versions(&vdb, &path, &chain)?;
+        }
+    }
+
+    blobs::restore(&blob_dir(&state), &hash, Path::new(&path))?;
+    index_single_path(&path, &state)?;
+    state.db.lock().map_err(|e| e.to_string())?.flush().map_err(|e| e.to_string())?;
+    Ok(format!("Restored {} to version {}", file_name_of(&path), &hash[..12]))
+}
+
 // ── SNAPSHOTS ─────────────────────────────────────────────────
 
 #[tauri::command]
@@ -621,42 +1911,283 @@ fn get_snapshots(state: State<'_, AppState>) -> Result<Vec<SnapshotInfo>, String
     Ok(snaps)
 }
 
+fn remove_snapshot(vdb: &Db, timestamp: u64, name: &str) -> Result<(), String> {
+    vdb.remove(snapshot_key(timestamp, name).as_bytes()).map_err(|e| e.to_string())?;
+    vdb.drop_tree(manifest_tree_name(timestamp, name)).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 #[tauri::command]
 fn delete_snapshot(snapshot_name: String, timestamp: u64, state: State<'_, AppState>) -> Result<(), String> {
     let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
-    vdb.remove(format!("snapshot::{}::{}", timestamp, snapshot_name).as_bytes()).map_err(|e| e.to_string())?;
+    remove_snapshot(&vdb, timestamp, &snapshot_name)?;
     vdb.flush().map_err(|e| e.to_string())?;
     Ok(())
 }
 
+#[tauri::command]
+fn get_snapshot_manifest(snapshot_name: String, timestamp: u64, state: State<'_, AppState>) -> Result<Vec<ManifestEntry>, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    read_manifest(&vdb, timestamp, &snapshot_name)
+}
+
+// ── SNAPSHOT DIFF ──────────────────────────────────────────────
+// Same path + different hash = modified. A path that vanished whose hash
+// reappears at a new path = moved. Everything else is added / deleted.
+
+fn diff_manifests(old: Vec<ManifestEntry>, new: Vec<ManifestEntry>) -> Vec<DiffEntry> {
+    use std::collections::HashMap;
+    let old_by_path: HashMap<String, ManifestEntry> = old.into_iter().map(|e| (e.path.clone(), e)).collect();
+    let new_by_path: HashMap<String, ManifestEntry> = new.into_iter().map(|e| (e.path.clone(), e)).collect();
+
+    let mut changes = Vec::new();
+    let mut removed_by_hash: HashMap<String, Vec<&ManifestEntry>> = HashMap::new();
+    for (path, o) in &old_by_path {
+        match new_by_path.get(path) {
+            Some(n) if n.hash != o.hash => changes.push(DiffEntry {
+                change: "modified".to_string(), path: path.clone(), old_path: None,
+                hash: n.hash.clone(), old_hash: Some(o.hash.clone()), size: n.size,
+            }),
+            Some(_) => {}
+            None => removed_by_hash.entry(o.hash.clone()).or_default().push(o),
+        }
+    }
+
+    let mut added: Vec<&ManifestEntry> = new_by_path.values().filter(|n| !old_by_path.contains_key(&n.path)).collect();
+    added.sort_by(|a, b| a.path.cmp(&b.path));
+    for n in added {
+        match removed_by_hash.get_mut(&n.hash).and_then(|v| v.pop()) {
+            Some(o) => changes.push(DiffEntry {
+                change: "moved".to_string(), path: n.path.clone(), old_path: Some(o.path.clone()),
+                hash: n.hash.clone(), old_hash: Some(o.hash.clone()), size: n.size,
+            }),
+            None => changes.push(DiffEntry {
+                change: "added".to_string(), path: n.path.clone(), old_path: None,
+                hash: n.hash.clone(), old_hash: None, size: n.size,
+            }),
+        }
+    }
+
+    for o in removed_by_hash.into_values().flatten() {
+        changes.push(DiffEntry {
+            change: "deleted".to_string(), path: o.path.clone(), old_path: None,
+            hash: o.hash.clone(), old_hash: None, size: o.size,
+        });
+    }
+
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+// Builds a manifest of what is on disk now. Files whose size and mtime match
+// the reference manifest reuse its hash instead of being re-read.
+fn disk_manifest(folder_path: &str, reference: &[ManifestEntry], rules: &IgnoreRules, progress: &JobProgress) -> Result<Vec<ManifestEntry>, String> {
+    let known: std::collections::HashMap<&str, &ManifestEntry> = reference.iter().map(|e| (e.path.as_str(), e)).collect();
+    let mut entries = Vec::new();
+    for entry in ignores::walk(Path::new(folder_path), rules) {
+        if progress.is_cancelled() { return Err("Comparison cancelled".to_string()); }
+        if !entry.file_type().is_file() { continue; }
+        let path = entry.path().to_string_lossy().to_string();
+        let metadata = fs::metadata(entry.path()).ok();
+        let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+        let modified = format!("{:?}", metadata.as_ref().and_then(|m| m.modified().ok()).unwrap_or(SystemTime::now()));
+        progress.discovered(size);
+        progress.working_on(&path);
+        let (hash, hashed) = match known.get(path.as_str()) {
+            Some(k) if k.size == size && k.modified == modified => (k.hash.clone(), false),
+            _ => match calculate_hash(&path) {
+                Ok(h) => (h, true),
+                Err(e) => { progress.log(format!("{}: {}", path, e)); continue }
+            },
+        };
+        progress.processed(size, hashed);
+        entries.push(ManifestEntry { path, hash, size, modified });
+    }
+    progress.discovery_done();
+    Ok(entries)
+}
+
+#[tauri::command]
+fn diff_snapshots(
+    old_name: String, old_timestamp: u64,
+    new_name: String, new_timestamp: u64,
+    state: State<'_, AppState>,
+) -> Result<Vec<DiffEntry>, String> {
+    let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+    let old = read_manifest(&vdb, old_timestamp, &old_name)?;
+    let new = read_manifest(&vdb, new_timestamp, &new_name)?;
+    Ok(diff_manifests(old, new))
+}
+
+#[tauri::command]
+async fn diff_snapshot_with_disk(snapshot_name: String, timestamp: u64, job_id: Option<String>, app: AppHandle, state: State<'_, AppState>) -> Result<Vec<DiffEntry>, String> {
+    let label = format!("Compare '{}' with disk", snapshot_name);
+    state.jobs.run_inline(&app, job_id, "diff", label, |progress| {
+        let (info, old) = {
+            let vdb = state.version_db.lock().map_err(|e| e.to_string())?;
+            (read_snapshot_info(&vdb, timestamp, &snapshot_name)?, read_manifest(&vdb, timestamp, &snapshot_name)?)
+        };
+        if !Path::new(&info.folder_path).exists() {
+            return Err(format!("Folder no longer exists: {}", info.folder_path));
+        }
+        let rules = ignore_rules(&state)?;
+        let current = disk_manifest(&info.folder_path, &old, &rules, progress)?;
+        Ok(diff_manifests(old, current))
+    })
+}
+
+// ── MIGRATION ──────────────────────────────────────────────────
+// vault_v8 kept one FileMeta per hash, keyed by the bare hash. Its entries are
+// copied into the location index as "{hash}::{path}" and the old db is renamed
+// aside, so an interrupted migration simply runs again on the next launch.
+
+const MIGRATE_BATCH: usize = 1000;
+
+fn legacy_db_dir(state: &AppState) -> PathBuf {
+    state.data_dir.join("vault_v8")
+}
+
+fn migrate_v8(state: &AppState, progress: &JobProgress) -> Result<String, String> {
+    let old_dir = legacy_db_dir(state);
+    let old = sled::open(&old_dir).map_err(|e| e.to_string())?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let mut entries = Vec::new();
+    let mut migrated = 0usize;
+    for item in old.iter() {
+        let (k, v) = item.map_err(|e| e.to_string())?;
+        let Ok(meta) = serde_json::from_slice::<FileMeta>(&v) else { continue };
+        progress.processed(meta.size, false);
+        entries.push((String::from_utf8_lossy(&k).to_string(), meta));
+        if entries.len() == MIGRATE_BATCH {
+            put_locations(&db, &entries)?;
+            migrated += entries.len();
+            entries.clear();
+        }
+    }
+    put_locations(&db, &entries)?;
+    migrated += entries.len();
+    db.flush().map_err(|e| e.to_string())?;
+    drop(old);
+    fs::rename(&old_dir, state.data_dir.join("vault_v8_migrated")).map_err(|e| e.to_string())?;
+    // v8 kept one location per hash and no content types, so a rescan fills in the rest
+    progress.log(format!("Moved {} files from vault_v8 into the new index", migrated));
+    Ok(format!("Migrated {} files from the previous index. Rescan your folders to pick up duplicate copies and file types.", migrated))
+}
+
 // ── ENTRY POINT ────────────────────────────────────────────────
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // This executable doubles as the isolated PDF text extractor
+    if let Some(code) = fulltext::run_pdf_helper() { std::process::exit(code); }
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
             let data_dir = app.path().app_data_dir().unwrap();
             fs::create_dir_all(&data_dir).unwrap();
-            let db = sled::open(data_dir.join("vault_v8")).expect("DB open failed");
+            let db = sled::open(data_dir.join("vault_v9")).expect("DB open failed");
+            Indexes::open(&db).and_then(|idx| idx.ensure_built(&db)).expect("Index build failed");
             let version_db = sled::open(data_dir.join("vault_v8_history")).expect("History DB open failed");
-            app.manage(AppState { db: Mutex::new(db), version_db: Mutex::new(version_db) });
+            let handle = app.handle().clone();
+            let watcher = FolderWatcher::start(move |changes| {
+                let change = apply_fs_changes(&handle.state::<AppState>(), changes);
+                if !change.updated.is_empty() || !change.removed.is_empty() || !change.errors.is_empty() { let _ = handle.emit("index-changed", change); }
+            }).expect("Watcher start failed");
+            app.manage(AppState { db: Mutex::new(db), version_db: Mutex::new(version_db), data_dir, jobs: JobManager::default(), watcher, category_rules: Mutex::new(None) });
+            jobs::start_runners(app.handle(), jobs::RUNNER_THREADS);
+            let state = app.state::<AppState>();
+            // Shows up in the job history, so a failed migration is visible rather than silent
+            if legacy_db_dir(&state).is_dir() {
+                let _ = state.jobs.run_inline(app.handle(), None, "migrate", "Migrate vault_v8 index".to_string(), |progress| migrate_v8(&state, progress));
+            }
+            rewatch_saved_roots(&state);
+            schedule::start(app.handle(), run_due_schedules);
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             open_file, open_file_with, check_file_status,
-            get_file_properties, get_folder_properties,
+            get_file_properties, get_folder_properties, list_folder,
             add_single_file,
             rename_in_index, rename_folder,
             delete_to_bin, delete_folder_to_bin, delete_physical_file,
             move_file, move_folder,
             compress_to_zip, extract_zip,
-            find_similar_images,
-            start_auto_scan, get_all_stored_files, clear_vault,
-            get_deleted_files, clear_deleted_history,
-            get_snapshots, delete_snapshot,
+            find_exact_duplicates, find_similar_images, find_type_mismatches,
+            get_category_rules, set_category_rules, list_categories,
+            get_annotation, add_tags, remove_tags, set_rating, set_color_label, set_note,
+            list_tags, files_with_tag,
+            search_files, search_content,
+            verify_integrity, get_integrity_reports, delete_integrity_report,
+            start_auto_scan, cancel_scan, list_stored_files, stream_stored_files, clear_vault,
+            queue_scan, queue_similar_images, queue_compress, queue_move_folder,
+            list_jobs, get_job, cancel_job, clear_job_history,
+            get_watched_folders, watch_folder, unwatch_folder,
+            set_watch_schedule, get_retention_policy, set_retention_policy, prune_snapshots,
+            get_deleted_files, clear_deleted_history, restore_deleted,
+            get_blob_store_enabled, set_blob_store_enabled,
+            get_ignore_patterns, set_ignore_patterns,
+            get_file_versions, restore_version,
+            get_snapshots, delete_snapshot, get_snapshot_manifest,
+            diff_snapshots, diff_snapshot_with_disk,
         ])
         .run(tauri::generate_context!())
         .expect("tauri error")
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, hash: &str) -> ManifestEntry {
+        ManifestEntry { path: path.to_string(), hash: hash.to_string(), size: 1, modified: String::new() }
+    }
+
+    fn summary(changes: &[DiffEntry]) -> Vec<(&str, &str, Option<&str>)> {
+        changes.iter().map(|c| (c.change.as_str(), c.path.as_str(), c.old_path.as_deref())).collect()
+    }
+
+    #[test]
+    fn diff_classifies_each_kind_of_change() {
+        let old = vec![entry("/a/same", "1"), entry("/a/edited", "2"), entry("/a/gone", "3"), entry("/a/old_name", "4")];
+        let new = vec![entry("/a/same", "1"), entry("/a/edited", "22"), entry("/a/fresh", "5"), entry("/a/new_name", "4")];
+        let changes = diff_manifests(old, new);
+        assert_eq!(summary(&changes), vec![
+            ("modified", "/a/edited", None),
+            ("added", "/a/fresh", None),
+            ("deleted", "/a/gone", None),
+            ("moved", "/a/new_name", Some("/a/old_name")),
+        ]);
+        assert_eq!(changes[0].old_hash.as_deref(), Some("2"));
+        assert_eq!(changes[0].hash, "22");
+    }
+
+    #[test]
+    fn diff_pairs_each_removed_copy_with_one_new_path() {
+        // Two identical files left, three appeared: two moves and one genuine addition
+        let old = vec![entry("/x/1", "h"), entry("/x/2", "h")];
+        let new = vec![entry("/y/1", "h"), entry("/y/2", "h"), entry("/y/3", "h")];
+        let changes = diff_manifests(old, new);
+        let kinds: Vec<&str> = changes.iter().map(|c| c.change.as_str()).collect();
+        assert_eq!(kinds, vec!["moved", "moved", "added"]);
+        let mut sources: Vec<&str> = changes.iter().filter_map(|c| c.old_path.as_deref()).collect();
+        sources.sort_unstable();
+        assert_eq!(sources, vec!["/x/1", "/x/2"]);
+    }
+
+    #[test]
+    fn rebase_keeps_the_separator_and_survives_case_folding() {
+        assert_eq!(rebase_path("/photos/2024/a.jpg", "/photos", "/archive/photos"), "/archive/photos/2024/a.jpg");
+        assert_eq!(rebase_path("/photos/2024/a.jpg", "/photos/", "/archive"), "/archive/2024/a.jpg");
+        // 'İ' lower-cases to two characters, so byte offsets from a folded root would be off
+        assert_eq!(rebase_path("/data/İstanbul/a.jpg", "/data/i̇stanbul", "/trips"), "/trips/a.jpg");
+        assert_eq!(rebase_path("/data/İstanbul/a.jpg", "/data/İstanbul", "/trips/"), "/trips/a.jpg");
+    }
+
+    #[test]
+    fn diff_of_identical_manifests_is_empty() {
+        let files = vec![entry("/a", "1"), entry("/b", "2")];
+        assert!(diff_manifests(files.clone(), files).is_empty());
+        assert!(diff_manifests(Vec::new(), Vec::new()).is_empty());
+    }
+}
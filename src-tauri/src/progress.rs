@@ -0,0 +1,165 @@
+use serde::{Serialize, Deserialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+// ── PROGRESS + CANCELLATION ────────────────────────────────────
+// Long operations share an Arc<JobProgress> with the worker threads, which bump
+// the counters and append log lines; a Reporter turns them into "scan-progress" events.
+
+const REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+static NEXT_JOB: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProgressEvent {
+    pub job_id: String,
+    pub kind: String,
+    pub files_discovered: u64,
+    pub files_processed: u64,
+    pub files_hashed: u64,
+    pub files_unreadable: u64,
+    pub bytes_processed: u64,
+    pub bytes_total: u64,
+    pub current_path: String,
+    pub eta_secs: Option<u64>,
+    pub finished: bool,
+    pub cancelled: bool,
+}
+
+pub struct JobProgress {
+    pub job_id: String,
+    pub kind: String,
+    started: Instant,
+    cancelled: AtomicBool,
+    finished: AtomicBool,
+    discovery_done: AtomicBool,
+    discovered: AtomicU64,
+    processed: AtomicU64,
+    hashed: AtomicU64,
+    unreadable: AtomicU64,
+    bytes_done: AtomicU64,
+    bytes_total: AtomicU64,
+    current: Mutex<String>,
+    logs: Mutex<Vec<String>>,
+}
+
+pub fn new_job_id(kind: &str) -> String {
+    format!("{}-{}-{}", kind, crate::now_ts(), NEXT_JOB.fetch_add(1, Ordering::Relaxed))
+}
+
+impl JobProgress {
+    pub fn new(job_id: String, kind: &str) -> Self {
+        JobProgress {
+            job_id,
+            kind: kind.to_string(),
+            started: Instant::now(),
+            cancelled: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+            discovery_done: AtomicBool::new(false),
+            discovered: AtomicU64::new(0),
+            processed: AtomicU64::new(0),
+            hashed: AtomicU64::new(0),
+            unreadable: AtomicU64::new(0),
+            bytes_done: AtomicU64::new(0),
+            bytes_total: AtomicU64::new(0),
+            current: Mutex::new(String::new()),
+            logs: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn cancel(&self) { self.cancelled.store(true, Ordering::Relaxed); }
+    pub fn is_cancelled(&self) -> bool { self.cancelled.load(Ordering::Relaxed) }
+
+    pub fn discovered(&self, bytes: u64) {
+        self.discovered.fetch_add(1, Ordering::Relaxed);
+        self.bytes_total.fetch_add(bytes, Ordering::Relaxed);
+    }
+    pub fn discovery_done(&self) { self.discovery_done.store(true, Ordering::Relaxed); }
+
+    pub fn working_on(&self, path: &str) {
+        if let Ok(mut current) = self.current.lock() { current.clear(); current.push_str(path); }
+    }
+
+    pub fn processed(&self, bytes: u64, hashed: bool) {
+        self.processed.fetch_add(1, Ordering::Relaxed);
+        self.bytes_done.fetch_add(bytes, Ordering::Relaxed);
+        if hashed { self.hashed.fetch_add(1, Ordering::Relaxed); }
+    }
+
+    // A file that could not be read still counts towards progress; `reason` goes to the log
+    pub fn unreadable(&self, bytes: u64, reason: impl Into<String>) {
+        self.processed.fetch_add(1, Ordering::Relaxed);
+        self.bytes_done.fetch_add(bytes, Ordering::Relaxed);
+        self.unreadable.fetch_add(1, Ordering::Relaxed);
+        self.log(reason);
+    }
+
+    pub fn log(&self, line: impl Into<String>) {
+        if let Ok(mut logs) = self.logs.lock() { logs.push(line.into()); }
+    }
+
+    pub fn logs(&self) -> Vec<String> {
+        self.logs.lock().map(|l| l.clone()).unwrap_or_default()
+    }
+
+    // (processed, hashed, unreadable)
+    pub fn counts(&self) -> (u64, u64, u64) {
+        (self.processed.load(Ordering::Relaxed), self.hashed.load(Ordering::Relaxed), self.unreadable.load(Ordering::Relaxed))
+    }
+
+    pub fn snapshot(&self) -> ProgressEvent {
+        let bytes_done = self.bytes_done.load(Ordering::Relaxed);
+        let bytes_total = self.bytes_total.load(Ordering::Relaxed);
+        // The total is only known once the walk has finished
+        let eta_secs = if self.discovery_done.load(Ordering::Relaxed) && bytes_done > 0 {
+            let elapsed = self.started.elapsed().as_secs_f64();
+            Some((elapsed * bytes_total.saturating_sub(bytes_done) as f64 / bytes_done as f64) as u64)
+        } else {
+            None
+        };
+        ProgressEvent {
+            job_id: self.job_id.clone(),
+            kind: self.kind.clone(),
+            files_discovered: self.discovered.load(Ordering::Relaxed),
+            files_processed: self.processed.load(Ordering::Relaxed),
+            files_hashed: self.hashed.load(Ordering::Relaxed),
+            files_unreadable: self.unreadable.load(Ordering::Relaxed),
+            bytes_processed: bytes_done,
+            bytes_total,
+            current_path: self.current.lock().map(|c| c.clone()).unwrap_or_default(),
+            eta_secs,
+            finished: self.finished.load(Ordering::Relaxed),
+            cancelled: self.is_cancelled(),
+        }
+    }
+}
+
+// Emits "scan-progress" events for a job until dropped, then sends a final one
+pub struct Reporter {
+    progress: Arc<JobProgress>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Reporter {
+    pub fn start(app: &AppHandle, progress: &Arc<JobProgress>) -> Self {
+        let app = app.clone();
+        let shared = Arc::clone(progress);
+        let handle = std::thread::spawn(move || loop {
+            let finished = shared.finished.load(Ordering::Relaxed);
+            let _ = app.emit("scan-progress", shared.snapshot());
+            if finished { break; }
+            std::thread::sleep(REPORT_INTERVAL);
+        });
+        Reporter { progress: Arc::clone(progress), handle: Some(handle) }
+    }
+}
+
+impl Drop for Reporter {
+    fn drop(&mut self) {
+        self.progress.finished.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() { let _ = handle.join(); }
+    }
+}
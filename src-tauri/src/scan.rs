@@ -0,0 +1,159 @@
+use serde::{Serialize, Deserialize};
+use std::fs;
+use std::sync::mpsc::sync_channel;
+use std::sync::{Arc, Mutex};
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::calculate_hash;
+use crate::filetype::{self, FileType};
+use crate::ignores::{self, IgnoreRules};
+use crate::progress::JobProgress;
+
+// ── SCAN PIPELINE ──────────────────────────────────────────────
+// walker thread → bounded queue → N hash workers → bounded queue → caller
+// Per-file work that reads the file again (text extraction) runs on the workers
+// through `on_file`; the caller receives results in batches so sled writes can be grouped.
+// Cancelling stops the walker and workers; everything already hashed is still
+// handed to the caller, so the index only ever holds fully processed files.
+
+const QUEUE_PER_THREAD: usize = 64;
+const WRITE_BATCH: usize = 256;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct StatEntry {
+    pub size: u64,
+    pub modified: String,
+    pub inode: u64,
+    pub hash: String,
+    #[serde(default)]
+    pub file_type: Option<FileType>,
+}
+
+pub struct ScanOptions {
+    pub threads: usize,
+    pub force_full: bool,
+}
+
+pub struct ScannedFile {
+    pub path: String,
+    pub size: u64,
+    pub modified: String,
+    pub inode: u64,
+    pub hash: String,
+    pub file_type: FileType,
+    pub cached: bool,
+}
+
+struct PendingFile {
+    path: String,
+    size: u64,
+    modified: String,
+    inode: u64,
+}
+
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
+}
+
+#[cfg(unix)]
+pub fn inode_of(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+pub fn inode_of(_metadata: &fs::Metadata) -> u64 {
+    0
+}
+
+// Trusts the cached entry only when size, mtime and inode are all unchanged
+pub fn cached_stat(cache: &sled::Tree, path: &str, size: u64, modified: &str, inode: u64) -> Option<StatEntry> {
+    let v = cache.get(path.as_bytes()).ok()??;
+    let stat: StatEntry = serde_json::from_slice(&v).ok()?;
+    (stat.size == size && stat.modified == modified && stat.inode == inode).then_some(stat)
+}
+
+fn hash_pending(file: PendingFile, cache: &sled::Tree, force_full: bool) -> Result<ScannedFile, String> {
+    let cached = if force_full { None } else { cached_stat(cache, &file.path, file.size, &file.modified, file.inode) };
+    let (hash, file_type, cached) = match cached {
+        // Entries cached before type detection existed get sniffed once here
+        Some(stat) => (stat.hash, stat.file_type.unwrap_or_else(|| filetype::detect(&file.path)), true),
+        None => (calculate_hash(&file.path)?, filetype::detect(&file.path), false),
+    };
+    Ok(ScannedFile { path: file.path, size: file.size, modified: file.modified, inode: file.inode, hash, file_type, cached })
+}
+
+// Walks `folder` (skipping ignored paths), hashing files on `opts.threads` workers.
+// `on_file` runs on the worker that hashed each file. `on_batch` runs on the calling
+// thread; an error from it stops the walk and is returned.
+pub fn scan_folder<G, F>(folder: &str, cache: &sled::Tree, opts: &ScanOptions, rules: &IgnoreRules, progress: &JobProgress, on_file: &G, mut on_batch: F) -> Result<(), String>
+where
+    G: Fn(&ScannedFile) + Sync,
+    F: FnMut(Vec<ScannedFile>) -> Result<(), String>,
+{
+    let threads = opts.threads.max(1);
+    let (pending_tx, pending_rx) = sync_channel::<PendingFile>(threads * QUEUE_PER_THREAD);
+    let (done_tx, done_rx) = sync_channel::<ScannedFile>(threads * QUEUE_PER_THREAD);
+    // Workers share the receiver; once they all exit it is dropped and the walker stops too
+    let pending_rx = Arc::new(Mutex::new(pending_rx));
+
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            for entry in ignores::walk(Path::new(folder), rules) {
+                if progress.is_cancelled() { break; }
+                if !entry.file_type().is_file() { continue; }
+                let metadata = fs::metadata(entry.path()).ok();
+                let file = PendingFile {
+                    path: entry.path().to_string_lossy().to_string(),
+                    size: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+                    modified: format!("{:?}", metadata.as_ref().and_then(|m| m.modified().ok()).unwrap_or(SystemTime::now())),
+                    inode: metadata.as_ref().map(inode_of).unwrap_or(0),
+                };
+                progress.discovered(file.size);
+                if pending_tx.send(file).is_err() { break; }
+            }
+            progress.discovery_done();
+        });
+
+        for _ in 0..threads {
+            let pending_rx = Arc::clone(&pending_rx);
+            let done_tx = done_tx.clone();
+            scope.spawn(move || loop {
+                let next = match pending_rx.lock() {
+                    Ok(rx) => rx.recv(),
+                    Err(_) => break,
+                };
+                let Ok(file) = next else { break };
+                if progress.is_cancelled() { break; }
+                progress.working_on(&file.path);
+                let (size, path) = (file.size, file.path.clone());
+                match hash_pending(file, cache, opts.force_full) {
+                    Ok(scanned) => {
+                        on_file(&scanned);
+                        progress.processed(size, !scanned.cached);
+                        if done_tx.send(scanned).is_err() { break; }
+                    }
+                    // Left out of this scan's batches, so it is neither indexed nor tombstoned
+                    Err(e) => progress.unreadable(size, format!("Unreadable {}: {}", path, e)),
+                }
+            });
+        }
+        drop(pending_rx);
+        drop(done_tx);
+
+        let mut batch = Vec::with_capacity(WRITE_BATCH);
+        let mut result = Ok(());
+        for scanned in done_rx.iter() {
+            batch.push(scanned);
+            if batch.len() >= WRITE_BATCH {
+                result = on_batch(std::mem::take(&mut batch));
+                if result.is_err() { break; }
+            }
+        }
+        if result.is_ok() && !batch.is_empty() { result = on_batch(batch); }
+        drop(done_rx);
+        if result.is_ok() && progress.is_cancelled() { result = Err("Scan cancelled".to_string()); }
+        result
+    })
+}
@@ -0,0 +1,98 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use tauri::AppHandle;
+
+// ── SCHEDULED SNAPSHOTS ────────────────────────────────────────
+// Watched folders may carry a schedule ("hourly" | "daily" | "weekly"). A ticker
+// thread starts due scans; retention keeps the newest scheduled snapshot in each
+// of the last N hours, M days and K weeks and lets everything else go.
+
+const TICK: Duration = Duration::from_secs(60);
+const HOUR: u64 = 3600;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RetentionPolicy {
+    pub hourly: usize,
+    pub daily: usize,
+    pub weekly: usize,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy { hourly: 24, daily: 7, weekly: 4 }
+    }
+}
+
+pub fn interval_secs(schedule: &str) -> Option<u64> {
+    match schedule {
+        "hourly" => Some(HOUR),
+        "daily" => Some(DAY),
+        "weekly" => Some(WEEK),
+        _ => None,
+    }
+}
+
+// Timestamps to keep out of `timestamps`; a snapshot kept by any bucket survives
+pub fn snapshots_to_keep(timestamps: &[u64], policy: &RetentionPolicy) -> HashSet<u64> {
+    let mut newest_first = timestamps.to_vec();
+    newest_first.sort_unstable_by(|a, b| b.cmp(a));
+    let mut keep = HashSet::new();
+    for (bucket, limit) in [(HOUR, policy.hourly), (DAY, policy.daily), (WEEK, policy.weekly)] {
+        let mut seen = HashSet::new();
+        for &ts in &newest_first {
+            if seen.len() >= limit { break; }
+            if seen.insert(ts / bucket) { keep.insert(ts); }
+        }
+    }
+    keep
+}
+
+pub fn start(app: &AppHandle, tick: fn(&AppHandle)) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(TICK);
+        tick(&app);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hours(of: &[u64]) -> HashSet<u64> {
+        of.iter().map(|h| h * HOUR + 10).collect()
+    }
+
+    #[test]
+    fn keeps_the_newest_snapshot_per_bucket() {
+        let taken: Vec<u64> = (0..72).map(|h| h * HOUR + 10).collect();
+        let policy = RetentionPolicy { hourly: 2, daily: 2, weekly: 1 };
+        // The last two hours, plus the end of the day before; day and week 0 are already held by hour 71
+        assert_eq!(snapshots_to_keep(&taken, &policy), hours(&[71, 70, 47]));
+    }
+
+    #[test]
+    fn buckets_count_distinct_periods_not_snapshots() {
+        // Three snapshots in hour 5 fill one hourly slot, so the second goes to hour 2
+        let taken: Vec<u64> = [5 * HOUR + 50, 5 * HOUR + 30, 5 * HOUR + 10, 2 * HOUR + 10].to_vec();
+        let policy = RetentionPolicy { hourly: 2, daily: 0, weekly: 0 };
+        assert_eq!(snapshots_to_keep(&taken, &policy), HashSet::from([5 * HOUR + 50, 2 * HOUR + 10]));
+    }
+
+    #[test]
+    fn weekly_buckets_reach_past_the_daily_ones() {
+        let taken: Vec<u64> = [0, 2 * DAY, 9 * DAY, 10 * DAY].to_vec();
+        let policy = RetentionPolicy { hourly: 0, daily: 1, weekly: 2 };
+        assert_eq!(snapshots_to_keep(&taken, &policy), HashSet::from([10 * DAY, 2 * DAY]));
+    }
+
+    #[test]
+    fn an_empty_policy_keeps_nothing() {
+        let policy = RetentionPolicy { hourly: 0, daily: 0, weekly: 0 };
+        assert!(snapshots_to_keep(&[HOUR, DAY], &policy).is_empty());
+        assert!(snapshots_to_keep(&[], &RetentionPolicy::default()).is_empty());
+    }
+}
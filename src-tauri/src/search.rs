@@ -0,0 +1,316 @@
+use globset::{GlobBuilder, GlobMatcher};
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+
+use crate::FileMeta;
+
+// ── SEARCH QUERY LANGUAGE ──────────────────────────────────────
+//   name:*.pdf  size:>10MB  size:1MB..1GB  modified:<2025-01-01  modified:2024-03
+//   category:image  tag:tax  path:~/Projects  mime:image/*
+// Terms are ANDed; a leading '-' negates one; a bare word matches the file name.
+// Values with spaces go in quotes: name:"tax return*"
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SearchHit {
+    pub hash: String,
+    pub meta: FileMeta,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SearchPage {
+    pub total: usize,
+    pub offset: usize,
+    pub items: Vec<SearchHit>,
+}
+
+enum Filter {
+    Name(GlobMatcher),
+    NameContains(String),
+    Size(u64, u64),          // inclusive range
+    Modified(u64, u64),      // unix seconds, inclusive range
+    Category(String),
+    Tag(String),
+    Path(String),            // normalised prefix
+    Mime(String),
+    MimePrefix(String),      // mime:image/*
+}
+
+struct Term {
+    negated: bool,
+    filter: Filter,
+}
+
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+// Splits on whitespace, keeping "quoted values" (and key:"quoted values") together
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() { tokens.push(std::mem::take(&mut current)); }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() { tokens.push(current); }
+    tokens
+}
+
+fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim().to_uppercase();
+    let split = s.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let n: f64 = num.parse().map_err(|_| format!("Bad size: {}", s))?;
+    let mult: u64 = match unit.trim() {
+        "" | "B" => 1,
+        "K" | "KB" => 1 << 10,
+        "M" | "MB" => 1 << 20,
+        "G" | "GB" => 1 << 30,
+        "T" | "TB" => 1 << 40,
+        u => return Err(format!("Unknown size unit: {}", u)),
+    };
+    Ok((n * mult as f64) as u64)
+}
+
+// Days since 1970-01-01 for a civil date (proleptic Gregorian)
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (m as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+// YYYY, YYYY-MM or YYYY-MM-DD → [start, end] in unix seconds (UTC). Spans that end
+// before 1970 are rejected; one that straddles it starts at 0.
+fn parse_date_span(s: &str) -> Result<(u64, u64), String> {
+    let parts: Vec<&str> = s.split('-').collect();
+    let bad = || format!("Bad date: {} (use YYYY-MM-DD)", s);
+    let year: i64 = parts.first().ok_or_else(bad)?.parse().map_err(|_| bad())?;
+    let month: u32 = match parts.get(1) { Some(m) => m.parse().map_err(|_| bad())?, None => 0 };
+    let day: u32 = match parts.get(2) { Some(d) => d.parse().map_err(|_| bad())?, None => 0 };
+    if parts.len() > 3 || month > 12 || day > 31 || (parts.len() > 1 && month == 0) || (parts.len() > 2 && day == 0) { return Err(bad()); }
+    let (start, end) = match (month, day) {
+        (0, _) => (days_from_civil(year, 1, 1), days_from_civil(year + 1, 1, 1)),
+        (m, 0) => (days_from_civil(year, m, 1), if m == 12 { days_from_civil(year + 1, 1, 1) } else { days_from_civil(year, m + 1, 1) }),
+        (m, d) => { let s = days_from_civil(year, m, d); (s, s + 1) }
+    };
+    if end <= 0 { return Err(format!("Dates before 1970 aren't supported: {}", s)); }
+    Ok(((start.max(0) * 86400) as u64, ((end * 86400) as u64).saturating_sub(1)))
+}
+
+// `>x`, `>=x`, `<x`, `<=x`, `=x`, `x..y` or `x` over values where `span` turns x into [lo, hi]
+fn parse_range<F: Fn(&str) -> Result<(u64, u64), String>>(value: &str, span: F) -> Result<(u64, u64), String> {
+    if let Some((a, b)) = value.split_once("..") {
+        let lo = if a.is_empty() { 0 } else { span(a)?.0 };
+        let hi = if b.is_empty() { u64::MAX } else { span(b)?.1 };
+        return Ok((lo, hi));
+    }
+    Ok(if let Some(v) = value.strip_prefix(">=") { (span(v)?.0, u64::MAX) }
+    else if let Some(v) = value.strip_prefix("<=") { (0, span(v)?.1) }
+    else if let Some(v) = value.strip_prefix('>') { (span(v)?.1.saturating_add(1), u64::MAX) }
+    // Nothing is below zero, so `<0` (or `<1970-01-01`) is an empty range
+    else if let Some(v) = value.strip_prefix('<') { span(v)?.0.checked_sub(1).map_or((1, 0), |hi| (0, hi)) }
+    else { span(value.strip_prefix('=').unwrap_or(value))? })
+}
+
+pub fn normalize_path(p: &str) -> String {
+    let expanded = match p.strip_prefix('~') {
+        Some(rest) => {
+            let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")).unwrap_or_default();
+            format!("{}{}", home, rest)
+        }
+        None => p.to_string(),
+    };
+    expanded.replace('\\', "/").to_lowercase()
+}
+
+// FileMeta.modified is SystemTime's Debug output: "SystemTime { tv_sec: N, .. }" on unix,
+// "SystemTime { intervals: N }" (100ns ticks since 1601) on Windows
+pub fn modified_secs(modified: &str) -> Option<u64> {
+    let number_after = |key: &str| -> Option<u64> {
+        let rest = &modified[modified.find(key)? + key.len()..];
+        rest.trim_start().split(|c: char| !c.is_ascii_digit()).next()?.parse().ok()
+    };
+    if let Some(secs) = number_after("tv_sec:") { return Some(secs); }
+    number_after("intervals:").map(|i| (i / 10_000_000).saturating_sub(11_644_473_600))
+}
+
+fn parse_term(token: &str) -> Result<Term, String> {
+    let (negated, token) = match token.strip_prefix('-') {
+        Some(rest) if !rest.is_empty() => (true, rest),
+        _ => (false, token),
+    };
+    let filter = match token.split_once(':') {
+        Some((key, value)) if !value.is_empty() => match key.to_lowercase().as_str() {
+            "name" if value.contains(['*', '?', '[']) => {
+                let glob = GlobBuilder::new(value).case_insensitive(true).build().map_err(|e| e.to_string())?;
+                Filter::Name(glob.compile_matcher())
+            }
+            "name" => Filter::NameContains(value.to_lowercase()),
+            "size" => { let (lo, hi) = parse_range(value, |v| parse_size(v).map(|n| (n, n)))?; Filter::Size(lo, hi) }
+            "modified" => { let (lo, hi) = parse_range(value, parse_date_span)?; Filter::Modified(lo, hi) }
+            "category" | "type" => Filter::Category(value.to_lowercase()),
+            "tag" => Filter::Tag(crate::annotations::normalize_tag(value).ok_or_else(|| format!("Bad tag: {}", value))?),
+            "path" | "in" => Filter::Path(normalize_path(value)),
+            "mime" => match value.strip_suffix('*') {
+                Some(prefix) => Filter::MimePrefix(prefix.to_lowercase()),
+                None => Filter::Mime(value.to_lowercase()),
+            },
+            other => return Err(format!("Unknown filter '{}:'", other)),
+        },
+        _ => Filter::NameContains(token.to_lowercase()),
+    };
+    Ok(Term { negated, filter })
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let terms = tokenize(query).iter().map(|t| parse_term(t)).collect::<Result<Vec<_>, _>>()?;
+        Ok(Query { terms })
+    }
+
+    // Tags the caller must resolve to hash sets before calling `matches`
+    pub fn tags(&self) -> Vec<String> {
+        self.terms.iter().filter_map(|t| match &t.filter { Filter::Tag(tag) => Some(tag.clone()), _ => None }).collect()
+    }
+
+    // Tags, category and path prefix every match must have; used to narrow candidates
+    pub fn required_tags(&self) -> Vec<String> {
+        self.terms.iter().filter_map(|t| match &t.filter { Filter::Tag(tag) if !t.negated => Some(tag.clone()), _ => None }).collect()
+    }
+
+    pub fn category(&self) -> Option<String> {
+        self.terms.iter().find_map(|t| match &t.filter { Filter::Category(c) if !t.negated => Some(c.clone()), _ => None })
+    }
+
+    pub fn path_prefix(&self) -> Option<String> {
+        self.terms.iter().find_map(|t| match &t.filter { Filter::Path(p) if !t.negated => Some(p.clone()), _ => None })
+    }
+
+    // `tagged(tag)` is the set of hashes carrying that tag
+    pub fn matches<'a, F>(&self, hash: &str, meta: &FileMeta, tagged: F) -> bool
+    where
+        F: Fn(&str) -> Option<&'a HashSet<String>>,
+    {
+        let name = crate::file_name_of(&meta.path);
+        self.terms.iter().all(|term| {
+            let hit = match &term.filter {
+                Filter::Name(glob) => glob.is_match(&name),
+                Filter::NameContains(s) => name.to_lowercase().contains(s),
+                Filter::Size(lo, hi) => (*lo..=*hi).contains(&meta.size),
+                Filter::Modified(lo, hi) => modified_secs(&meta.modified).is_some_and(|t| (*lo..=*hi).contains(&t)),
+                Filter::Category(c) => meta.category.to_lowercase() == *c,
+                Filter::Tag(tag) => tagged(tag).is_some_and(|set| set.contains(hash)),
+                Filter::Path(prefix) => meta.path.replace('\\', "/").to_lowercase().starts_with(prefix),
+                Filter::Mime(m) => meta.mime == *m,
+                Filter::MimePrefix(p) => meta.mime.starts_with(p),
+            };
+            hit != term.negated
+        })
+    }
+}
+
+// sort_by: "name" | "size" | "modified" | "path" (default)
+pub fn sort_hits(hits: &mut [SearchHit], sort_by: &str, descending: bool) {
+    match sort_by {
+        "name" => hits.sort_by_cached_key(|h| crate::file_name_of(&h.meta.path).to_lowercase()),
+        "size" => hits.sort_by_key(|h| h.meta.size),
+        "modified" => hits.sort_by_key(|h| modified_secs(&h.meta.modified).unwrap_or(0)),
+        _ => hits.sort_by_cached_key(|h| h.meta.path.to_lowercase()),
+    }
+    if descending { hits.reverse(); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, size: u64, modified_secs: u64) -> FileMeta {
+        FileMeta {
+            path: path.to_string(),
+            size,
+            modified: format!("SystemTime {{ tv_sec: {}, tv_nsec: 0 }}", modified_secs),
+            category: "document".to_string(),
+            mime: "application/pdf".to_string(),
+        }
+    }
+
+    fn hits(query: &str, meta: &FileMeta) -> bool {
+        Query::parse(query).unwrap().matches("h", meta, |_| None)
+    }
+
+    #[test]
+    fn name_globs_words_and_negation() {
+        let f = file("/docs/Tax Return 2024.PDF", 10, 0);
+        assert!(hits("name:*.pdf", &f));
+        assert!(hits(r#"name:"tax return*""#, &f));
+        assert!(hits("return", &f));
+        assert!(hits("name:2024", &f));
+        assert!(!hits("name:*.txt", &f));
+        assert!(!hits("-name:*.pdf", &f));
+        assert!(hits("tax -invoice", &f));
+    }
+
+    #[test]
+    fn sizes_and_units() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("1.5k").unwrap(), 1536);
+        assert_eq!(parse_size("10MB").unwrap(), 10 << 20);
+        assert_eq!(parse_size("2 GB").unwrap(), 2 << 30);
+        let ten_mb = file("/a", 10 << 20, 0);
+        assert!(!hits("size:>10MB", &ten_mb));
+        assert!(hits("size:>=10MB", &ten_mb));
+        assert!(hits("size:<=10MB", &ten_mb));
+        assert!(!hits("size:<10MB", &ten_mb));
+        assert!(hits("size:10MB", &ten_mb));
+        assert!(hits("size:1MB..1GB", &ten_mb));
+        assert!(hits("size:1MB..", &ten_mb));
+        assert!(!hits("size:..1MB", &ten_mb));
+        assert!(!hits("size:<0", &file("/a", 0, 0)));
+    }
+
+    #[test]
+    fn date_spans() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        // 2024-03-01 and 2024-04-01 at 00:00 UTC
+        let (march, april) = (1_709_251_200, 1_711_929_600);
+        assert_eq!(parse_date_span("2024-03").unwrap(), (march, april - 1));
+        assert_eq!(parse_date_span("2024-03-01").unwrap(), (march, march + 86399));
+        assert_eq!(parse_date_span("1970").unwrap().0, 0);
+        assert!(hits("modified:2024-03", &file("/a", 1, march)));
+        assert!(!hits("modified:2024-03", &file("/a", 1, april)));
+        assert!(hits("modified:<2024-04-01", &file("/a", 1, april - 1)));
+        assert!(!hits("modified:<2024-04-01", &file("/a", 1, april)));
+        assert!(hits("modified:>2024-03", &file("/a", 1, april)));
+        assert!(hits("modified:2024-01..2024-03", &file("/a", 1, march)));
+        assert!(hits("modified:2023..", &file("/a", 1, march)));
+    }
+
+    #[test]
+    fn pre_epoch_dates_never_underflow() {
+        assert!(parse_date_span("1969").is_err());
+        assert!(parse_date_span("1969-12-31").is_err());
+        assert!(Query::parse("modified:1969").is_err());
+        assert!(!hits("modified:<1970-01-01", &file("/a", 1, 0)));
+        assert!(hits("modified:<=1970-01-01", &file("/a", 1, 0)));
+    }
+
+    #[test]
+    fn bad_input_is_an_error() {
+        for query in ["size:10XB", "size:big", "modified:2024-13", "modified:2024-00", "modified:2024-03-00",
+                      "modified:yesterday", "modified:2024-03-01-01", "colour:red", "name:[", r#"tag:" ""#] {
+            assert!(Query::parse(query).is_err(), "{} should not parse", query);
+        }
+        assert!(Query::parse("").unwrap().matches("h", &file("/a", 1, 0), |_| None));
+    }
+}
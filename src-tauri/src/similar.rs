@@ -0,0 +1,523 @@
+use serde::{Serialize, Deserialize};
+use sled::Db;
+use std::collections::HashMap;
+
+use crate::imagehash::{self, Algorithm, Hashes, Transform};
+use crate::progress::JobProgress;
+
+// ── IMAGE FINGERPRINTS ─────────────────────────────────────────
+// Computed when a file detected as an image is indexed and stored in the vault's
+// "phash" tree under its content hash, so identical copies share one decode and
+// edited bytes simply get a new entry; entries for content no longer at any
+// indexed location are pruned when a scan releases them.
+//   value: width u32, height u32, then per Transform::ALL phash/ahash/dhash/whash u64, all LE;
+//          empty when the image could not be decoded
+// Grouping finds candidate pairs through a multi-index hash table, checks them
+// against every requested algorithm and joins matches with union-find, so the
+// work grows with the near matches rather than with every pair of images.
+
+const ENCODED_LEN: usize = 8 + 8 * 4 * 8;
+
+#[derive(Clone, Copy)]
+pub struct ImageFingerprint {
+    pub width: u32,
+    pub height: u32,
+    pub oriented: [Hashes; 8],   // after each Transform, in Transform::ALL order
+}
+
+impl ImageFingerprint {
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(ENCODED_LEN);
+        out.extend_from_slice(&self.width.to_le_bytes());
+        out.extend_from_slice(&self.height.to_le_bytes());
+        for h in &self.oriented {
+            for v in [h.phash, h.ahash, h.dhash, h.whash] { out.extend_from_slice(&v.to_le_bytes()); }
+        }
+        out
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != ENCODED_LEN { return None; }
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        let u64_at = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        let oriented = std::array::from_fn(|t| {
+            let at = 8 + t * 32;
+            Hashes { phash: u64_at(at), ahash: u64_at(at + 8), dhash: u64_at(at + 16), whash: u64_at(at + 24) }
+        });
+        Some(ImageFingerprint { width: u32_at(0), height: u32_at(4), oriented })
+    }
+}
+
+// Decoded the right way up: a photo whose EXIF says "rotate 90°" is hashed as it is shown
+pub fn fingerprint(path: &str) -> Result<ImageFingerprint, String> {
+    use image::ImageDecoder;
+    let mut decoder = image::ImageReader::open(path).map_err(|e| e.to_string())?
+        .with_guessed_format().map_err(|e| e.to_string())?
+        .into_decoder().map_err(|e| e.to_string())?;
+    let orientation = decoder.orientation().unwrap_or(image::metadata::Orientation::NoTransforms);
+    let mut img = image::DynamicImage::from_decoder(decoder).map_err(|e| e.to_string())?;
+    img.apply_orientation(orientation);
+    Ok(ImageFingerprint { width: img.width(), height: img.height(), oriented: imagehash::compute(&img) })
+}
+
+// Max differing bits (of 64) for two images to match under `algorithm`
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct HashThreshold {
+    pub algorithm: Algorithm,
+    pub threshold: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HashMatch {
+    pub algorithm: Algorithm,
+    pub distance: u32,
+    pub matched: bool,      // distance within that algorithm's threshold
+}
+
+// Why `hash` is in its group: the member it matched directly, the best image when possible.
+// `transform` turns `hash`'s image into one that looks like `matched_to`'s.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SimilarPair {
+    pub hash: String,
+    pub matched_to: String,
+    pub transform: Transform,
+    pub hashes: Vec<HashMatch>,
+    pub similarity_pct: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SimilarGroup {
+    pub best: String,
+    pub others: Vec<String>,
+    pub similarity_pct: u32,
+    pub pairs: Vec<SimilarPair>,
+}
+
+pub fn hamming(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+pub struct ImageIndex {
+    tree: sled::Tree,
+}
+
+impl ImageIndex {
+    pub fn open(db: &Db) -> Result<Self, String> {
+        Ok(ImageIndex { tree: db.open_tree("phash").map_err(|e| e.to_string())? })
+    }
+
+    // Some(None) for an image that failed to decode, so it isn't retried. Entries in
+    // an older layout count as missing and get recomputed.
+    fn entry(&self, hash: &str) -> Option<Option<ImageFingerprint>> {
+        let v = self.tree.get(hash.as_bytes()).ok().flatten()?;
+        if v.is_empty() { Some(None) } else { ImageFingerprint::decode(&v).map(Some) }
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.entry(hash).is_some()
+    }
+
+    pub fn get(&self, hash: &str) -> Option<ImageFingerprint> {
+        self.entry(hash).flatten()
+    }
+
+    fn insert(&self, hash: &str, fp: Option<&ImageFingerprint>) -> Result<(), String> {
+        let encoded = fp.map(|f| f.encode()).unwrap_or_default();
+        self.tree.insert(hash.as_bytes(), encoded).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    pub fn index_file(&self, hash: &str, path: &str) -> Result<(), String> {
+        if self.contains(hash) { return Ok(()); }
+        match fingerprint(path) {
+            Ok(fp) => self.insert(hash, Some(&fp)),
+            Err(e) => { self.insert(hash, None)?; Err(format!("No fingerprint for {}: {}", path, e)) }
+        }
+    }
+
+    // Fingerprints (hash, path) pairs not stored yet on `threads` workers; returns the failures
+    pub fn index_files(&self, files: &[(String, String)], threads: usize, progress: &JobProgress) -> Vec<String> {
+        let mut seen = std::collections::HashSet::new();
+        let todo: Vec<&(String, String)> = files.iter().filter(|(h, _)| seen.insert(h) && !self.contains(h)).collect();
+        let next = std::sync::atomic::AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads.max(1)).map(|_| scope.spawn(|| {
+                let mut errors = Vec::new();
+                while !progress.is_cancelled() {
+                    let i = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    let Some((hash, path)) = todo.get(i) else { break };
+                    progress.working_on(path);
+                    if let Err(e) = self.index_file(hash, path) { errors.push(e); }
+                }
+                errors
+            })).collect();
+            workers.into_iter().flat_map(|w| w.join().unwrap_or_default()).collect()
+        })
+    }
+
+    // Drops fingerprints for those of `candidates` no longer at any indexed location
+    pub fn prune<'a, I, F>(&self, candidates: I, is_live: F) -> Result<usize, String>
+    where
+        I: IntoIterator<Item = &'a str>,
+        F: Fn(&str) -> bool,
+    {
+        let mut dropped = 0;
+        for hash in candidates {
+            if is_live(hash) { continue; }
+            if self.tree.remove(hash.as_bytes()).map_err(|e| e.to_string())?.is_some() { dropped += 1; }
+        }
+        Ok(dropped)
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        self.tree.clear().map_err(|e| e.to_string())
+    }
+}
+
+// ── MULTI-INDEX HASHING ────────────────────────────────────────
+// Each hash is cut into four 16-bit chunks with a bucket table per chunk. Two
+// hashes within t bits differ by at most t/4 bits in at least one chunk, so a
+// lookup only probes the buckets that close to its own chunks and verifies
+// what it finds there, instead of comparing against every other hash.
+
+const CHUNKS: usize = 4;
+const CHUNK_VALUES: usize = 1 << 16;
+
+fn chunk(value: u64, c: usize) -> usize {
+    ((value >> (16 * c)) & 0xffff) as usize
+}
+
+// Every 16-bit value within `radius` bits of `key`
+fn neighbours(key: usize, radius: u32) -> Vec<usize> {
+    let mut out = vec![key];
+    let mut frontier = vec![(key, 0)];    // (value, lowest bit still free to flip)
+    for _ in 0..radius.min(16) {
+        let mut next = Vec::new();
+        for &(v, from) in &frontier {
+            for bit in from..16 {
+                out.push(v ^ (1 << bit));
+                next.push((v ^ (1 << bit), bit + 1));
+            }
+        }
+        frontier = next;
+    }
+    out
+}
+
+pub struct HashIndex {
+    // Bucket b holds entries[starts[b]..starts[b + 1]], b = chunk * CHUNK_VALUES + chunk value.
+    // Values are kept inline so verifying a candidate never leaves the bucket.
+    starts: Vec<usize>,
+    entries: Vec<(u64, usize)>,
+}
+
+impl HashIndex {
+    pub fn new(values: &[u64]) -> Self {
+        let bucket = |v: u64, c: usize| c * CHUNK_VALUES + chunk(v, c);
+        let mut starts = vec![0usize; CHUNKS * CHUNK_VALUES + 1];
+        for &v in values { for c in 0..CHUNKS { starts[bucket(v, c) + 1] += 1; } }
+        for b in 1..starts.len() { starts[b] += starts[b - 1]; }
+        let mut fill = starts.clone();
+        let mut entries = vec![(0, 0); values.len() * CHUNKS];
+        for (i, &v) in values.iter().enumerate() {
+            for c in 0..CHUNKS {
+                let b = bucket(v, c);
+                entries[fill[b]] = (v, i);
+                fill[b] += 1;
+            }
+        }
+        HashIndex { starts, entries }
+    }
+
+    // Every (i, j) with i < j where one of `queries[i]` is within `threshold` bits of value j,
+    // or one of `queries[j]` of value i: a turned copy may sit on either side. Each is listed once.
+    pub fn pairs(&self, queries: &[&[u64]], threshold: u32, threads: usize) -> Vec<(usize, usize)> {
+        let radius = threshold / CHUNKS as u32;
+        let probes = neighbours(0, radius);
+        // A pair can turn up under several chunks; only the first chunk close enough reports it
+        let close = |a: u64, b: u64, c: usize| (chunk(a ^ b, c) as u64).count_ones() <= radius;
+        let from = |i: usize, v: u64, found: &mut Vec<(usize, usize)>| {
+            for c in 0..CHUNKS {
+                let key = chunk(v, c);
+                for &flip in &probes {
+                    let b = c * CHUNK_VALUES + (key ^ flip);
+                    for &(w, j) in &self.entries[self.starts[b]..self.starts[b + 1]] {
+                        if j == i { continue; }
+                        if hamming(v, w) <= threshold && !(0..c).any(|earlier| close(v, w, earlier)) { found.push((i.min(j), i.max(j))); }
+                    }
+                }
+            }
+        };
+        let threads = threads.max(1);
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads).map(|t| scope.spawn(move || {
+                let mut found = Vec::new();
+                for i in (t..queries.len()).step_by(threads) {
+                    for &v in queries[i] { from(i, v, &mut found); }
+                }
+                found
+            })).collect();
+            let mut found: Vec<_> = workers.into_iter().flat_map(|w| w.join().unwrap_or_default()).collect();
+            found.sort_unstable();
+            found.dedup();
+            found
+        })
+    }
+}
+
+// ── UNION-FIND ─────────────────────────────────────────────────
+
+pub struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    pub fn new(n: usize) -> Self {
+        UnionFind { parent: (0..n).collect(), size: vec![1; n] }
+    }
+
+    pub fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    pub fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b { return; }
+        if self.size[a] < self.size[b] { std::mem::swap(&mut a, &mut b); }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+}
+
+// A matching pair: `transform` turns a's image into b's, and its distance under each
+// criterion, in criteria order
+pub struct Edge {
+    pub a: usize,
+    pub b: usize,
+    pub transform: Transform,
+    pub distances: Vec<u32>,
+}
+
+impl Edge {
+    // The transform that turns `from`'s image (a or b) into the other one
+    pub fn transform_from(&self, from: usize) -> Transform {
+        if from == self.a { self.transform } else { self.transform.inverse() }
+    }
+}
+
+pub struct Group {
+    pub members: Vec<usize>,   // indices into the input
+    pub edges: Vec<Edge>,
+}
+
+impl Group {
+    // (member, member it matched, edge) for everyone but `root`, breadth-first from
+    // `root` so members that match it directly are tied to it
+    pub fn links(&self, root: usize) -> Vec<(usize, usize, &Edge)> {
+        let mut touching: HashMap<usize, Vec<&Edge>> = HashMap::new();
+        for e in &self.edges {
+            touching.entry(e.a).or_default().push(e);
+            touching.entry(e.b).or_default().push(e);
+        }
+        let mut reached = std::collections::HashSet::from([root]);
+        let mut queue = std::collections::VecDeque::from([root]);
+        let mut links = Vec::with_capacity(self.members.len().saturating_sub(1));
+        while let Some(at) = queue.pop_front() {
+            for &e in touching.get(&at).map(|v| v.as_slice()).unwrap_or_default() {
+                let other = if e.a == at { e.b } else { e.a };
+                if reached.insert(other) { links.push((other, at, e)); queue.push_back(other); }
+            }
+        }
+        links
+    }
+}
+
+// Groups of 2+ inputs linked (transitively) by matching pairs. `hashes[k][i][t]` is input
+// i's hash under criterion k after transform t (Transform::ALL order); a pair matches when
+// all of its distances are within `thresholds`, or with `require_all` false, when any one
+// is. Without `any_orientation` only the untransformed hashes are compared.
+pub fn group(hashes: &[Vec<[u64; 8]>], thresholds: &[u32], require_all: bool, any_orientation: bool, threads: usize) -> Vec<Group> {
+    let Some(n) = hashes.first().map(|h| h.len()) else { return Vec::new() };
+    let transforms: &[Transform] = if any_orientation { &Transform::ALL } else { &[Transform::Identity] };
+
+    // Every match is a candidate from the strictest criterion when all must hold,
+    // and from one criterion or another when any may
+    let searched: Vec<usize> = if require_all {
+        (0..thresholds.len()).min_by_key(|&k| thresholds[k]).into_iter().collect()
+    } else {
+        (0..thresholds.len()).collect()
+    };
+    let mut candidates: Vec<(usize, usize)> = searched.iter().flat_map(|&k| {
+        let upright: Vec<u64> = hashes[k].iter().map(|h| h[0]).collect();
+        let queries: Vec<&[u64]> = hashes[k].iter().map(|h| &h[..transforms.len()]).collect();
+        HashIndex::new(&upright).pairs(&queries, thresholds[k], threads)
+    }).collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    // The transform with the closest match wins; turning a into b is checked from both
+    // sides since hashes of a turned image are near, not exactly, a permutation
+    let mut uf = UnionFind::new(n);
+    let mut edges = Vec::new();
+    for (a, b) in candidates {
+        let best = transforms.iter().filter_map(|&t| {
+            let distances: Vec<u32> = hashes.iter()
+                .map(|h| hamming(h[a][t as usize], h[b][0]).min(hamming(h[b][t.inverse() as usize], h[a][0])))
+                .collect();
+            let mut within = distances.iter().zip(thresholds).map(|(d, limit)| d <= limit);
+            let matched = if require_all { within.all(|w| w) } else { within.any(|w| w) };
+            matched.then(|| (distances.iter().sum::<u32>(), t, distances))
+        }).min_by_key(|(total, _, _)| *total);
+        if let Some((_, transform, distances)) = best {
+            uf.union(a, b);
+            edges.push(Edge { a, b, transform, distances });
+        }
+    }
+
+    let mut groups: HashMap<usize, Group> = HashMap::new();
+    for i in 0..n {
+        groups.entry(uf.find(i)).or_insert_with(|| Group { members: Vec::new(), edges: Vec::new() }).members.push(i);
+    }
+    for e in edges {
+        if let Some(g) = groups.get_mut(&uf.find(e.a)) { g.edges.push(e); }
+    }
+    groups.into_values().filter(|g| g.members.len() > 1).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Deterministic 64-bit values (splitmix64)
+    fn values(n: usize, seed: u64) -> Vec<u64> {
+        let mut state = seed;
+        (0..n).map(|_| {
+            state = state.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        }).collect()
+    }
+
+    fn sorted(mut pairs: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+        pairs.sort_unstable();
+        pairs
+    }
+
+    #[test]
+    fn neighbours_cover_the_radius_exactly_once() {
+        let near = neighbours(0x1234, 2);
+        assert_eq!(near.len(), 1 + 16 + 120);
+        assert!(near.iter().all(|&v| v < CHUNK_VALUES && ((v ^ 0x1234) as u64).count_ones() <= 2));
+        let distinct: std::collections::HashSet<_> = near.iter().collect();
+        assert_eq!(distinct.len(), near.len());
+    }
+
+    #[test]
+    fn index_finds_the_same_pairs_as_brute_force() {
+        // Random hashes plus near copies with a few bits flipped, spread across chunks
+        let mut hashes = values(200, 7);
+        let flips = values(200, 99);
+        for (i, f) in flips.iter().enumerate() {
+            let mut copy = hashes[i];
+            for bit in 0..(i % 14) { copy ^= 1u64 << ((f >> (6 * (bit % 10))) & 63); }
+            hashes.push(copy);
+        }
+        let queries: Vec<&[u64]> = hashes.iter().map(std::slice::from_ref).collect();
+        for threshold in [0, 3, 8, 12] {
+            let mut expected = Vec::new();
+            for i in 0..hashes.len() {
+                for j in i + 1..hashes.len() {
+                    if hamming(hashes[i], hashes[j]) <= threshold { expected.push((i, j)); }
+                }
+            }
+            let found = sorted(HashIndex::new(&hashes).pairs(&queries, threshold, 3));
+            assert_eq!(found, expected, "threshold {}", threshold);
+        }
+    }
+
+    #[test]
+    fn index_matches_any_of_the_query_hashes() {
+        let stored = [0u64, u64::MAX, 0xffff_0000_ffff_0000];
+        // Entry 0 only matches entry 2 through its second query value
+        let queries: Vec<&[u64]> = vec![&[0x0f0f_0f0f_0f0f_0f0f, 0xffff_0000_ffff_0001], &[u64::MAX], &[0xffff_0000_ffff_0000]];
+        assert_eq!(sorted(HashIndex::new(&stored).pairs(&queries, 4, 1)), vec![(0, 2)]);
+    }
+
+    #[test]
+    fn union_find_joins_transitively() {
+        let mut uf = UnionFind::new(6);
+        uf.union(0, 1);
+        uf.union(2, 3);
+        uf.union(1, 3);
+        uf.union(4, 4);
+        let root = uf.find(0);
+        assert!([1, 2, 3].iter().all(|&i| uf.find(i) == root));
+        assert_ne!(uf.find(4), root);
+        assert_ne!(uf.find(4), uf.find(5));
+    }
+
+    #[test]
+    fn group_links_chains_and_leaves_strangers_out() {
+        // a–b and b–c are 4 bits apart, a–c 8, d far from all
+        let upright = [0u64, 0xf, 0xff, 0xffff_ffff_0000_0000];
+        let hashes = vec![upright.iter().map(|&h| [h; 8]).collect::<Vec<_>>()];
+        let groups = group(&hashes, &[4], true, false, 2);
+        assert_eq!(groups.len(), 1);
+        let mut members = groups[0].members.clone();
+        members.sort_unstable();
+        assert_eq!(members, vec![0, 1, 2]);
+        assert_eq!(groups[0].edges.len(), 2);
+        assert_eq!(groups[0].links(0).len(), 2);
+    }
+
+    #[test]
+    fn group_with_any_criterion_accepts_one_close_hash() {
+        let close = vec![[0u64; 8], [0x7; 8]];
+        let far = vec![[0u64; 8], [u64::MAX; 8]];
+        let hashes = vec![close, far];
+        assert!(group(&hashes, &[4, 4], true, false, 1).is_empty());
+        let groups = group(&hashes, &[4, 4], false, false, 1);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].edges[0].distances, vec![3, 64]);
+    }
+
+    #[test]
+    fn group_finds_turned_copies_only_when_asked() {
+        // b is a turned 90° clockwise: b's upright hash is a's Rotate90 hash
+        let mut a = [0x1111_2222_3333_4444u64; 8];
+        a[Transform::Rotate90 as usize] = 0xaaaa_bbbb_cccc_dddd;
+        let mut b = [0xaaaa_bbbb_cccc_dddd; 8];
+        b[Transform::Rotate270 as usize] = 0x1111_2222_3333_4444;
+        let hashes = vec![vec![a, b]];
+        assert!(group(&hashes, &[2], true, false, 1).is_empty());
+        let groups = group(&hashes, &[2], true, true, 1);
+        assert_eq!(groups.len(), 1);
+        let edge = &groups[0].edges[0];
+        assert_eq!(edge.transform, Transform::Rotate90);
+        assert_eq!(edge.distances, vec![0]);
+        assert_eq!(edge.transform_from(1), Transform::Rotate270);
+    }
+
+    #[test]
+    fn group_finds_a_turned_copy_stored_before_its_original() {
+        // Only the later entry's transforms reach the earlier one: a turned 90° clockwise is b
+        let b = [0xaaaa_bbbb_cccc_dddd; 8];
+        let mut a = [0x1111_2222_3333_4444u64; 8];
+        a[Transform::Rotate90 as usize] = 0xaaaa_bbbb_cccc_dddd;
+        let upright: Vec<u64> = vec![b[0], a[0]];
+        let queries: Vec<&[u64]> = vec![&b, &a];
+        assert_eq!(HashIndex::new(&upright).pairs(&queries, 2, 1), vec![(0, 1)]);
+        let groups = group(&[vec![b, a]], &[2], true, true, 1);
+        assert_eq!(groups.len(), 1);
+        let edge = &groups[0].edges[0];
+        assert_eq!(edge.distances, vec![0]);
+        assert_eq!(edge.transform_from(1), Transform::Rotate90);
+    }
+}
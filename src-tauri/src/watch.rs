@@ -0,0 +1,77 @@
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use notify::RecursiveMode;
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, RecommendedCache};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// ── LIVE WATCHING ──────────────────────────────────────────────
+// One recursive watch per registered root. Raw events are debounced (renames
+// are stitched back into from/to pairs) and handed over as FsChange batches.
+
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+pub enum FsChange {
+    Changed(String),
+    Removed(String),
+    Renamed(String, String),
+}
+
+pub struct FolderWatcher {
+    debouncer: Mutex<Debouncer<notify::RecommendedWatcher, RecommendedCache>>,
+}
+
+fn path_str(p: &Path) -> String {
+    p.to_string_lossy().to_string()
+}
+
+fn collect_changes(events: Vec<notify_debouncer_full::DebouncedEvent>) -> Vec<FsChange> {
+    let mut changes = Vec::new();
+    let mut changed = HashSet::new();
+    for ev in events {
+        let paths: Vec<String> = ev.event.paths.iter().map(|p| path_str(p)).collect();
+        match ev.event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if paths.len() == 2 => {
+                changes.push(FsChange::Renamed(paths[0].clone(), paths[1].clone()));
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) | EventKind::Remove(_) => {
+                changes.extend(paths.into_iter().map(FsChange::Removed));
+            }
+            // mtime/permission changes alone never change the content hash
+            EventKind::Modify(ModifyKind::Metadata(_)) => {}
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) | EventKind::Create(_) | EventKind::Modify(_) => {
+                for p in paths {
+                    if changed.insert(p.clone()) { changes.push(FsChange::Changed(p)); }
+                }
+            }
+            _ => {}
+        }
+    }
+    changes
+}
+
+impl FolderWatcher {
+    pub fn start<F>(on_change: F) -> Result<Self, String>
+    where
+        F: Fn(Vec<FsChange>) + Send + 'static,
+    {
+        let debouncer = new_debouncer(DEBOUNCE, None, move |result: DebounceEventResult| {
+            if let Ok(events) = result {
+                let changes = collect_changes(events);
+                if !changes.is_empty() { on_change(changes); }
+            }
+        }).map_err(|e| e.to_string())?;
+        Ok(FolderWatcher { debouncer: Mutex::new(debouncer) })
+    }
+
+    pub fn watch(&self, root: &str) -> Result<(), String> {
+        let mut debouncer = self.debouncer.lock().map_err(|e| e.to_string())?;
+        debouncer.watch(Path::new(root), RecursiveMode::Recursive).map_err(|e| e.to_string())
+    }
+
+    pub fn unwatch(&self, root: &str) -> Result<(), String> {
+        let mut debouncer = self.debouncer.lock().map_err(|e| e.to_string())?;
+        debouncer.unwatch(Path::new(root)).map_err(|e| e.to_string())
+    }
+}
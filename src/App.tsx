@@ -1,5 +1,6 @@
-import { useState, useEffect, useMemo } from "react";
-import { invoke } from "@tauri-apps/api/core";
+import { useState, useEffect, useMemo, useRef } from "react";
+import { invoke, Channel } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { open, save } from "@tauri-apps/plugin-dialog";
 
 // Components
@@ -22,19 +23,22 @@ import TimelineView    from "./views/TimelineView";
 
 // Types & helpers
 import type {
-  FileMeta, DeletedEntry, SnapshotInfo,
-  FileProperties, FolderProperties,
+  FileMeta, DeletedEntry, SnapshotInfo, DuplicateGroup, ProgressEvent,
+  FileProperties, FolderProperties, SearchPage, SearchHit, IndexChange,
   CtxItem, CtxMenu, PanelInfo, ViewMode, AppActions,
 } from "./types";
-import { getCat } from "./helpers";
+import { getCat, fmtSize, BUILTIN_CATEGORIES } from "./helpers";
 
 import "./App.css";
 
 export default function App() {
   // ── Core state ────────────────────────────────────────────────
-  const [allFiles, setAllFiles]         = useState<[string, string][]>([]);
+  const [allFiles, setAllFiles]         = useState<FileMeta[]>([]);
+  const [duplicateGroups, setDuplicateGroups] = useState<DuplicateGroup[]>([]);
+  const [categories, setCategories] = useState<string[]>([]);
   const [currentPath, setCurrentPath]   = useState<string[]>([]);
   const [searchQuery, setSearchQuery]   = useState("");
+  const [searchResults, setSearchResults] = useState<FileMeta[]>([]);
   const [filterType, setFilterType]     = useState("all");
   const [viewMode, setViewMode]         = useState<ViewMode>("browser");
   const [status, setStatus]             = useState("Ready");
@@ -43,6 +47,7 @@ export default function App() {
   const [panelInfo, setPanelInfo]       = useState<PanelInfo | null>(null);
   const [ctxMenu, setCtxMenu]           = useState<CtxMenu | null>(null);
   const [clipboard, setClipboard]       = useState<CtxItem | null>(null);
+  const [progress, setProgress]         = useState<ProgressEvent | null>(null);
 
   // ── Modal state ───────────────────────────────────────────────
   const [renameModal, setRenameModal]   = useState<{ hash: string|null; path: string; currentName: string; isFolder: boolean } | null>(null);
@@ -52,6 +57,7 @@ export default function App() {
   const [pasteModal, setPasteModal]     = useState(false);
 
   const [snapshotNameInput, setSnapshotNameInput] = useState("");
+  const [forceFullScan, setForceFullScan] = useState(false);
   const [renameInput, setRenameInput]   = useState("");
   const [openWithInput, setOpenWithInput] = useState("");
   const [pasteDestInput, setPasteDestInput] = useState("");
@@ -59,8 +65,24 @@ export default function App() {
 
   // ── Data loading ──────────────────────────────────────────────
 
+  const toFileMeta = ({ hash, meta }: SearchHit): FileMeta =>
+    ({ ...meta, hash, name: meta.path.split(/[\\/]/).pop() || meta.path });
+
+  // The vault arrives in chunks over a channel instead of one huge IPC payload
+  const loadAllFiles = async () => {
+    const files: FileMeta[] = [];
+    const onChunk = new Channel<SearchHit[]>();
+    onChunk.onmessage = chunk => { for (const hit of chunk) files.push(toFileMeta(hit)); };
+    await invoke("stream_stored_files", { onChunk });
+    return files;
+  };
+
   const refreshVault = async () => {
-    try { setAllFiles(await invoke("get_all_stored_files") as [string, string][]); }
+    try {
+      setAllFiles(await loadAllFiles());
+      setDuplicateGroups(await invoke("find_exact_duplicates", { sortBy: "wasted" }) as DuplicateGroup[]);
+      setCategories(await invoke("list_categories") as string[]);
+    }
     catch (e) { setStatus(`Error: ${e}`); }
   };
 
@@ -72,6 +94,49 @@ export default function App() {
   };
 
   useEffect(() => { refreshVault(); refreshHistory(); }, []);
+  useEffect(() => {
+    const un = listen<ProgressEvent>("scan-progress", e => setProgress(e.payload.finished ? null : e.payload));
+    return () => { un.then(f => f()); };
+  }, []);
+  // The watcher listener is registered once, so it reads the latest vault through refs
+  const allFilesRef = useRef(allFiles);
+  const dupHashesRef = useRef(new Set<string>());
+  useEffect(() => { allFilesRef.current = allFiles; }, [allFiles]);
+  useEffect(() => { dupHashesRef.current = new Set(duplicateGroups.map(g => g.hash)); }, [duplicateGroups]);
+  useEffect(() => {
+    // The backend watcher applied disk changes under a watched folder; patch just those paths
+    const un = listen<IndexChange>("index-changed", async e => {
+      const { updated, removed, errors } = e.payload;
+      const touched = new Set([...removed, ...updated.map(h => h.meta.path)]);
+      const hashes = new Set(updated.map(h => h.hash));
+      // Duplicate groups only move when a touched file was in one, or now shares its hash
+      const dupesAffected = hashes.size < updated.length || allFilesRef.current.some(f =>
+        touched.has(f.path) ? dupHashesRef.current.has(f.hash) : hashes.has(f.hash));
+      setAllFiles(files => [...files.filter(f => !touched.has(f.path)), ...updated.map(toFileMeta)]);
+      if (errors.length) {
+        console.warn("Watcher could not apply some changes:", errors);
+        setStatus(`Watcher: ${errors.length} change(s) failed — ${errors[0]}`);
+      }
+      try {
+        if (dupesAffected) setDuplicateGroups(await invoke("find_exact_duplicates", { sortBy: "wasted" }) as DuplicateGroup[]);
+      } catch (err) { setStatus(`Error: ${err}`); }
+      if (removed.length) refreshHistory();
+    });
+    return () => { un.then(f => f()); };
+  }, []);
+  useEffect(() => {
+    // Queries run in the backend; debounce so each keystroke doesn't trigger a search
+    if (!searchQuery.trim()) { setSearchResults([]); return; }
+    const query = filterType === "all" ? searchQuery : `${searchQuery} category:${filterType}`;
+    const t = setTimeout(async () => {
+      try {
+        const page = await invoke("search_files", { query, sortBy: "name", limit: 500 }) as SearchPage;
+        setSearchResults(page.items.map(toFileMeta));
+        setStatus(page.total > page.items.length ? `Showing ${page.items.length} of ${page.total} matches` : `${page.total} matches`);
+      } catch (e) { setSearchResults([]); setStatus(`Search: ${e}`); }
+    }, 200);
+    return () => clearTimeout(t);
+  }, [searchQuery, filterType, allFiles]);
   useEffect(() => {
     const h = () => setCtxMenu(null);
     window.addEventListener("click", h);
@@ -81,47 +146,30 @@ export default function App() {
   // ── Computed ──────────────────────────────────────────────────
 
   const parsedFiles = useMemo(() =>
-    allFiles.map(([hash, json]) => {
-      try { const m = JSON.parse(json); return { ...m, hash, name: m.path.split(/[\\/]/).pop() || m.path } as FileMeta; }
-      catch { return null; }
-    }).filter(f => f && (filterType === "all" || f.category === filterType)) as FileMeta[],
+    filterType === "all" ? allFiles : allFiles.filter(f => f.category === filterType),
   [allFiles, filterType]);
 
-  const allParsed = useMemo(() =>
-    allFiles.map(([hash, json]) => {
-      try { const m = JSON.parse(json); return { ...m, hash, name: m.path.split(/[\\/]/).pop() || m.path } as FileMeta; }
-      catch { return null; }
-    }).filter(Boolean) as FileMeta[],
-  [allFiles]);
-
-  const duplicateGroups = useMemo(() => {
-    const g: Record<string, FileMeta[]> = {};
-    parsedFiles.forEach(f => { if (!g[f.hash]) g[f.hash] = []; g[f.hash].push(f); });
-    return Object.values(g).filter(a => a.length > 1);
-  }, [parsedFiles]);
-
   const browserItems = useMemo(() => {
     const map = new Map<string, any>();
-    const q = searchQuery.toLowerCase();
+    if (searchQuery.trim()) {
+      searchResults.forEach(f => map.set(f.path, { ...f, isFolder: false }));
+      return Array.from(map.values());
+    }
     parsedFiles.forEach(f => {
       const parts = f.path.split(/[\\/]/).filter(Boolean);
-      if (q) {
-        if (f.name.toLowerCase().includes(q)) map.set(f.hash, { ...f, isFolder: false });
-      } else {
-        const under = currentPath.every((p, i) => parts[i] === p);
-        if (under && parts.length > currentPath.length) {
-          const name = parts[currentPath.length];
-          const isFolder = parts.length > currentPath.length + 1;
-          const key = isFolder ? `dir-${name}` : f.hash;
-          if (!map.has(key)) {
-            const folderPath = parts.slice(0, currentPath.length + 1).join("/");
-            map.set(key, { name, isFolder, hash: isFolder ? null : f.hash, path: f.path, category: f.category, folderPath });
-          }
+      const under = currentPath.every((p, i) => parts[i] === p);
+      if (under && parts.length > currentPath.length) {
+        const name = parts[currentPath.length];
+        const isFolder = parts.length > currentPath.length + 1;
+        const key = isFolder ? `dir-${name}` : f.path;
+        if (!map.has(key)) {
+          const folderPath = parts.slice(0, currentPath.length + 1).join("/");
+          map.set(key, { name, isFolder, hash: isFolder ? null : f.hash, path: f.path, category: f.category, folderPath });
         }
       }
     });
     return Array.from(map.values()).sort((a, b) => Number(b.isFolder) - Number(a.isFolder));
-  }, [parsedFiles, currentPath, searchQuery]);
+  }, [parsedFiles, currentPath, searchQuery, searchResults]);
 
   // ── Actions ───────────────────────────────────────────────────
 
@@ -146,7 +194,8 @@ export default function App() {
     const name = snapshotNameInput.trim() || new Date().toLocaleDateString("en-GB").replace(/\//g, "-");
     setIndexModal(null); setStatus("Indexing…");
     try {
-      const r = await invoke<string>("start_auto_scan", { folderPath: indexModal.folderPath, snapshotName: name });
+      const jobId = `scan-${Date.now()}`;
+      const r = await invoke<string>("start_auto_scan", { folderPath: indexModal.folderPath, snapshotName: name, forceFull: forceFullScan, jobId });
       await refreshVault(); await refreshHistory(); setStatus(r);
     } catch (e) { setStatus(`Error: ${e}`); }
   };
@@ -167,7 +216,7 @@ export default function App() {
     try {
       await invoke("delete_to_bin", { hash, path });
       await refreshVault(); await refreshHistory();
-      if (panelInfo?.type === "file" && panelInfo.data.hash === hash) setPanelInfo(null);
+      if (panelInfo?.type === "file" && panelInfo.data.path === path) setPanelInfo(null);
       setStatus("Moved to Recycle Bin.");
     } catch (e) { setStatus(`Error: ${e}`); }
   };
@@ -191,7 +240,7 @@ export default function App() {
     if (!renameModal || !renameInput.trim()) return;
     try {
       if (renameModal.isFolder) await invoke("rename_folder", { oldPath: renameModal.path, newName: renameInput.trim() });
-      else await invoke("rename_in_index", { hash: renameModal.hash, newName: renameInput.trim() });
+      else await invoke("rename_in_index", { hash: renameModal.hash, path: renameModal.path, newName: renameInput.trim() });
       await refreshVault(); setRenameModal(null); setRenameInput(""); setStatus("Renamed.");
     } catch (e) { alert(`Rename failed: ${e}`); }
   };
@@ -206,13 +255,13 @@ export default function App() {
     try {
       const r = clipboard.isFolder
         ? await invoke<string>("move_folder", { oldPath: clipboard.folderPath || clipboard.path, destinationParent: pasteDestInput.trim() })
-        : await invoke<string>("move_file", { hash: clipboard.hash, destinationFolder: pasteDestInput.trim() });
+        : await invoke<string>("move_file", { hash: clipboard.hash, path: clipboard.path, destinationFolder: pasteDestInput.trim() });
       await refreshVault(); setClipboard(null); setPasteModal(false); setStatus(`Moved to: ${r}`);
     } catch (e) { alert(`Move failed: ${e}`); }
   };
 
-  const showFileProps = async (hash: string) => {
-    try { setPanelInfo({ type: "file", data: await invoke<FileProperties>("get_file_properties", { hash }) }); }
+  const showFileProps = async (hash: string, path: string) => {
+    try { setPanelInfo({ type: "file", data: await invoke<FileProperties>("get_file_properties", { hash, path }) }); }
     catch (e) { setStatus(`Error: ${e}`); }
   };
 
@@ -277,6 +326,7 @@ export default function App() {
           smartDupCount={0}
           deletedCount={deleted.length}
           snapshotCount={snapshots.length}
+          customCategories={categories.filter(c => !BUILTIN_CATEGORIES.includes(c))}
           clipboard={clipboard}
           onPasteClick={() => setPasteModal(true)}
           onRefreshHistory={refreshHistory}
@@ -318,21 +368,26 @@ export default function App() {
             )}
             {viewMode === "smartdup" && (
               <SmartDedupView
-                allFiles={allParsed}
+                allFiles={allFiles}
                 onOpen={handleOpen}
                 onDeleteToBin={handleDeleteToBin}
                 onShowProps={showFileProps}
               />
             )}
             {viewMode === "history" && (
-              <HistoryView deleted={deleted} onRefresh={refreshHistory} />
+              <HistoryView
+                deleted={deleted}
+                onRefresh={refreshHistory}
+                onRestored={async msg => { await refreshVault(); await refreshHistory(); setStatus(msg); }}
+              />
             )}
             {viewMode === "snapshots" && (
               <SnapshotsView snapshots={snapshots} onRefresh={refreshHistory} setStatus={setStatus} />
             )}
             {viewMode === "timeline" && (
               <TimelineView
-                allFiles={allParsed}
+                allFiles={allFiles}
+                snapshots={snapshots}
                 onOpen={handleOpen}
                 onShowProps={showFileProps}
                 onCtx={handleCtx}
@@ -359,7 +414,17 @@ export default function App() {
       </div>
 
       <footer className="status-bar">
-        <span className="st-msg">{status}</span>
+        <span className="st-msg">
+          {progress
+            ? `${progress.kind === "scan" ? "Indexing" : "Comparing"} ${progress.files_processed}/${progress.files_discovered} · ${fmtSize(progress.bytes_processed)}${progress.eta_secs !== null ? ` · ETA ${progress.eta_secs}s` : ""}`
+            : status}
+        </span>
+        {progress && (
+          <button className="btn-xs" title={progress.current_path}
+            onClick={() => invoke("cancel_scan", { jobId: progress.job_id })}>
+            Cancel
+          </button>
+        )}
         <span className="st-sep">·</span>
         <span>{parsedFiles.length} files</span>
         <span className="st-sep">·</span>
@@ -389,6 +454,7 @@ export default function App() {
       {indexModal && (
         <IndexModal
           folderPath={indexModal.folderPath} snapshotName={snapshotNameInput}
+          forceFull={forceFullScan} onForceFullChange={setForceFullScan}
           onChange={setSnapshotNameInput} onConfirm={confirmIndex}
           onClose={() => setIndexModal(null)}
         />
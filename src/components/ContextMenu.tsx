@@ -54,7 +54,7 @@ export default function ContextMenu({ ctxMenu, currentPath, setCurrentPath, onCl
             <Ic.OpenWith /> Open With…
           </div>
           <div className="ctx-sep" />
-          <div className="ctx-row" onClick={wrap(() => item.hash && actions.showFileProps(item.hash))}>
+          <div className="ctx-row" onClick={wrap(() => item.hash && actions.showFileProps(item.hash, item.path))}>
             <Ic.Info /> Properties
           </div>
           <div className="ctx-row" onClick={wrap(() => {
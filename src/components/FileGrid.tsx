@@ -16,7 +16,7 @@ interface Props {
   parsedFiles:    FileMeta[];
   currentPath:    string[];
   onOpenFolder:   (name: string) => void;
-  onFileClick:    (hash: string) => void;
+  onFileClick:    (hash: string, path: string) => void;
   onFileDouble:   (path: string) => void;
   onContextMenu:  (e: React.MouseEvent, item: CtxItem) => void;
   onIndexFolder:  () => void;
@@ -49,10 +49,10 @@ export default function FileGrid({
     <>
       {items.map(item => (
         <div
-          key={item.isFolder ? `dir-${item.name}` : item.hash}
+          key={item.isFolder ? `dir-${item.name}` : item.path}
           className="grid-card"
           style={!item.isFolder ? { "--cc": catColor[item.category] || "#94a3b8" } as React.CSSProperties : {}}
-          onClick={() => !item.isFolder && item.hash && onFileClick(item.hash)}
+          onClick={() => !item.isFolder && item.hash && onFileClick(item.hash, item.path)}
           onDoubleClick={() =>
             item.isFolder ? onOpenFolder(item.name) : onFileDouble(item.path)
           }
@@ -64,7 +64,7 @@ export default function FileGrid({
           <div className="item-name" title={item.name}>{item.name}</div>
           {!item.isFolder && (
             <div className="card-size">
-              {fmtSize(parsedFiles.find(f => f.hash === item.hash)?.size || 0)}
+              {fmtSize(parsedFiles.find(f => f.path === item.path)?.size || 0)}
             </div>
           )}
         </div>
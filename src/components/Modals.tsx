@@ -44,9 +44,10 @@ export function RenameModal({ isFolder, value, onChange, onConfirm, onClose }: {
 
 // ── Index Folder Modal ─────────────────────────────────────────
 
-export function IndexModal({ folderPath, snapshotName, onChange, onConfirm, onClose }: {
-  folderPath: string; snapshotName: string;
+export function IndexModal({ folderPath, snapshotName, forceFull, onChange, onForceFullChange, onConfirm, onClose }: {
+  folderPath: string; snapshotName: string; forceFull: boolean;
   onChange: (v: string) => void;
+  onForceFullChange: (v: boolean) => void;
   onConfirm: () => void; onClose: () => void;
 }) {
   return (
@@ -62,6 +63,14 @@ export function IndexModal({ folderPath, snapshotName, onChange, onConfirm, onCl
           A snapshot is auto-saved with this name to track future deletions.
         </small>
       </div>
+      <div className="modal-field">
+        <label>
+          <input type="checkbox" checked={forceFull} onChange={e => onForceFullChange(e.target.checked)} /> Re-hash every file
+        </label>
+        <small className="modal-hint">
+          Unchanged files are normally skipped. Use this to verify everything.
+        </small>
+      </div>
       <div className="modal-footer">
         <button className="btn-ghost" onClick={onClose}>Cancel</button>
         <button className="btn-primary" onClick={onConfirm}>Start Indexing</button>
@@ -45,8 +45,10 @@ export default function PropertiesPanel({
               {([
                 ["Size",     fmtSize(p.size)],
                 ["Type",     p.category],
+                ["MIME",     p.mime || "unknown"],
                 ["Modified", parseModified(p.modified)],
                 ["Hash",     p.hash.substring(0, 20) + "…"],
+                ["Copies",   String(p.locations.length)],
                 ["Path",     p.path],
               ] as [string, string][]).map(([k, v]) => (
                 <div key={k} className="prop-row">
@@ -13,6 +13,7 @@ interface Props {
   smartDupCount:   number;
   deletedCount:    number;
   snapshotCount:   number;
+  customCategories: string[];
   clipboard:       CtxItem | null;
   onPasteClick:    () => void;
   onRefreshHistory: () => void;
@@ -25,13 +26,14 @@ const LIBRARY = [
   { key: "document", label: "Documents",  sym: "≡" },
   { key: "audio",    label: "Audio",      sym: "♪" },
   { key: "archive",  label: "Archives",   sym: "⊞" },
+  { key: "code",     label: "Code",       sym: "❮❯" },
 ] as const;
 
 export default function Sidebar({
   viewMode, setViewMode, filterType, setFilterType,
   setSearchQuery, setCurrentPath,
   totalFiles, dupCount, smartDupCount, deletedCount, snapshotCount,
-  clipboard, onPasteClick, onRefreshHistory,
+  customCategories, clipboard, onPasteClick, onRefreshHistory,
 }: Props) {
 
   const goLibrary = (key: string) => {
@@ -58,6 +60,14 @@ export default function Sidebar({
             {key === "all" && totalFiles > 0 && <span className="scnt">{totalFiles}</span>}
           </div>
         ))}
+        {customCategories.map(key => (
+          <div key={key}
+            className={`sidebar-item ${filterType === key && viewMode === "browser" ? "active" : ""}`}
+            onClick={() => goLibrary(key)}>
+            <span className="sico">◇</span>
+            {key}
+          </div>
+        ))}
       </div>
 
       <div className="sidebar-section">
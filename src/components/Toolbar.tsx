@@ -40,7 +40,7 @@ export default function Toolbar({
           <span className="sico-search"><Ic.Search /></span>
           <input
             className="search-input"
-            placeholder="Search files and folders..."
+            placeholder="Search — name:*.pdf size:>10MB tag:tax path:~/Projects"
             value={searchQuery}
             onChange={e => setSearchQuery(e.target.value)}
           />
@@ -14,16 +14,23 @@ export const fmtDate = (ts: number): string =>
   });
 
 export const getCat = (path: string): string => {
-  const ext = path.split(".").pop()?.toLowerCase() || "";
-  if (["jpg","jpeg","png","gif","webp","bmp","svg","ico"].includes(ext)) return "image";
-  if (["mp4","mkv","mov","avi","wmv","webm","flv"].includes(ext)) return "video";
-  if (["pdf","doc","docx","txt","xlsx","csv","pptx","md"].includes(ext)) return "document";
-  if (["mp3","wav","flac","aac","ogg","m4a"].includes(ext)) return "audio";
-  if (["zip","rar","7z","tar","gz","bz2"].includes(ext)) return "archive";
-  if (["exe","msi","dmg","deb"].includes(ext)) return "executable";
+  // Extension-only fallback; indexed files carry the backend's content-based category
+  const name = path.split(/[\\/]/).pop() || "";
+  const ext = name.includes(".") ? name.split(".").pop()!.toLowerCase() : "";
+  if (["jpg","jpeg","png","gif","webp","bmp","svg","ico","heic","heif","avif","tif","tiff","raw","cr2","nef","dng"].includes(ext)) return "image";
+  if (["mp4","mkv","mov","avi","wmv","webm","flv","m4v","mpg","mpeg","3gp"].includes(ext)) return "video";
+  if (["pdf","doc","docx","txt","xlsx","xls","csv","pptx","ppt","md","rtf","odt","ods","odp","epub"].includes(ext)) return "document";
+  if (["mp3","wav","flac","aac","ogg","m4a","opus","wma","aiff"].includes(ext)) return "audio";
+  if (["zip","rar","7z","tar","gz","bz2","xz","zst","tgz"].includes(ext)) return "archive";
+  if (["exe","msi","dmg","deb","rpm","apk","appimage"].includes(ext)) return "executable";
+  if (["rs","py","js","jsx","ts","tsx","c","h","cpp","hpp","cs","java","kt","go","rb","php","swift",
+       "sh","html","css","json","toml","yaml","yml","xml","sql"].includes(ext)) return "code";
   return "other";
 };
 
+// Mirrors categories::BUILTIN; anything else comes from user rules
+export const BUILTIN_CATEGORIES = ["image", "video", "document", "audio", "archive", "executable", "code", "other"];
+
 export const catColor: Record<string, string> = {
   image:      "#38bdf8",
   video:      "#a78bfa",
@@ -31,6 +38,7 @@ export const catColor: Record<string, string> = {
   audio:      "#fb923c",
   archive:    "#fbbf24",
   executable: "#f87171",
+  code:       "#f472b6",
   other:      "#94a3b8",
 };
 
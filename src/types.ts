@@ -5,6 +5,7 @@ export interface FileMeta {
   hash: string;
   name: string;
   category: string;
+  mime: string;
 }
 
 export interface DeletedEntry {
@@ -15,6 +16,7 @@ export interface DeletedEntry {
   category: string;
   deleted_at: number;
   snapshot_name: string;
+  blob_stored: boolean;
 }
 
 export interface SnapshotInfo {
@@ -22,6 +24,31 @@ export interface SnapshotInfo {
   timestamp: number;
   file_count: number;
   folder_path: string;
+  scheduled: boolean;
+}
+
+export interface FileVersion {
+  hash: string;
+  size: number;
+  modified: string;
+  seen_at: number;
+  blob_stored: boolean;
+}
+
+export interface ManifestEntry {
+  path: string;
+  hash: string;
+  size: number;
+  modified: string;
+}
+
+export interface DiffEntry {
+  change: "added" | "deleted" | "modified" | "moved";
+  path: string;
+  old_path: string | null;
+  hash: string;
+  old_hash: string | null;
+  size: number;
 }
 
 export interface FileProperties {
@@ -31,7 +58,28 @@ export interface FileProperties {
   hash: string;
   modified: string;
   category: string;
+  mime: string;
   exists_on_disk: boolean;
+  locations: string[];
+}
+
+export interface CategoryRule {
+  category: string;
+  extensions: string[];
+  mime: string | null;          // exact, or a prefix ending in "*"
+  path_glob: string | null;
+  min_size: number | null;
+  max_size: number | null;
+  name_regex: string | null;
+}
+
+export interface TypeMismatch {
+  hash: string;
+  path: string;
+  extension: string;
+  extension_category: string;
+  mime: string;
+  category: string;
 }
 
 export interface FolderProperties {
@@ -42,6 +90,43 @@ export interface FolderProperties {
   exists_on_disk: boolean;
 }
 
+export interface DuplicateGroup {
+  hash: string;
+  size: number;
+  category: string;
+  files: { path: string; size: number; modified: string; category: string }[];
+  wasted_bytes: number;
+}
+
+export interface ProgressEvent {
+  job_id: string;
+  kind: string;
+  files_discovered: number;
+  files_processed: number;
+  files_hashed: number;
+  files_unreadable: number;
+  bytes_processed: number;
+  bytes_total: number;
+  current_path: string;
+  eta_secs: number | null;
+  finished: boolean;
+  cancelled: boolean;
+}
+
+export interface JobSummary {
+  id: string;
+  kind: string;                 // "scan" | "similar" | "zip" | "move"
+  label: string;
+  state: "queued" | "running" | "done" | "failed" | "cancelled";
+  created_at: number;
+  started_at: number | null;
+  finished_at: number | null;
+  progress: ProgressEvent | null;
+  logs: string[];
+  result: unknown;              // full in "job-updated"; from list_jobs/get_job lists are reduced to counts
+  error: string | null;
+}
+
 export interface CtxItem {
   hash: string | null;
   path: string;
@@ -83,10 +168,141 @@ export interface AppActions {
   handleCut:                (item: CtxItem) => void;
   handleCompress:           (paths: string[]) => void;
   handleExtract:            (zipPath: string) => Promise<void>;
-  showFileProps:            (hash: string) => Promise<void>;
+  showFileProps:            (hash: string, path: string) => Promise<void>;
   showFolderProps:          (folderPath: string) => Promise<void>;
   setRenameModal:           (v: { hash: string|null; path: string; currentName: string; isFolder: boolean } | null) => void;
   setRenameInput:           (v: string) => void;
   setOpenWithModal:         (v: { path: string } | null) => void;
   setPasteModal:            (v: boolean) => void;
-}
\ No newline at end of file
+}
+
+export interface WatchedFolder {
+  path: string;
+  added_at: number;
+  schedule: "hourly" | "daily" | "weekly" | null;
+  last_run: number;
+}
+
+export interface RetentionPolicy {
+  hourly: number;
+  daily: number;
+  weekly: number;
+}
+
+export interface Annotation {
+  tags: string[];
+  rating: number | null;        // 1..5
+  color: "red" | "orange" | "yellow" | "green" | "blue" | "purple" | "gray" | null;
+  note: string;
+  updated_at: number;
+}
+
+export interface TagCount {
+  tag: string;
+  count: number;
+}
+
+// search_files — meta is the raw backend FileMeta (no hash/name fields)
+export interface SearchHit {
+  hash: string;
+  meta: Omit<FileMeta, "hash" | "name">;
+}
+
+// "index-changed" — the watcher's latest batch: where each touched path now stands
+export interface IndexChange {
+  updated: SearchHit[];
+  removed: string[];
+  errors: string[];
+}
+
+export interface SearchPage {
+  total: number;
+  offset: number;
+  items: SearchHit[];
+}
+
+// search_content — snippet parts with hit=true are the matched words
+export interface SnippetPart {
+  text: string;
+  hit: boolean;
+}
+
+export interface ContentHit {
+  hash: string;
+  meta: Omit<FileMeta, "hash" | "name">;
+  score: number;
+  snippet: SnippetPart[];
+}
+
+export interface ContentPage {
+  total: number;
+  offset: number;
+  items: ContentHit[];
+}
+
+// list_folder — one entry per immediate child; files/size cover everything beneath a folder
+export interface DirChild {
+  name: string;
+  path: string;
+  is_folder: boolean;
+  files: number;
+  size: number;
+}
+
+// list_stored_files — pass `next` back as `after` for the following page
+export interface FilePage {
+  items: SearchHit[];
+  next: string | null;
+}
+
+// verify_integrity job result / get_integrity_reports
+export interface IntegrityIssue {
+  path: string;
+  expected_hash: string;
+  actual_hash: string | null;
+  error: string | null;
+  recoverable: boolean;
+}
+
+export interface IntegrityReport {
+  folder_path: string;
+  timestamp: number;
+  finished_at: number;
+  verified: number;
+  changed: number;
+  corrupted: IntegrityIssue[];
+  missing: IntegrityIssue[];
+  unreadable: IntegrityIssue[];
+}
+
+export type HashAlgorithm = "phash" | "ahash" | "dhash" | "whash";
+
+export type ImageTransform =
+  | "identity" | "rotate90" | "rotate180" | "rotate270"
+  | "flip_horizontal" | "flip_vertical" | "transpose" | "transverse";
+
+export interface HashThreshold {
+  algorithm: HashAlgorithm;
+  threshold: number;    // max differing bits of 64
+}
+
+export interface HashMatch {
+  algorithm: HashAlgorithm;
+  distance: number;
+  matched: boolean;
+}
+
+export interface SimilarPair {
+  hash: string;
+  matched_to: string;   // the best image's hash when they match directly
+  transform: ImageTransform;  // turns this image into one that looks like matched_to
+  hashes: HashMatch[];
+  similarity_pct: number;
+}
+
+export interface SimilarGroup {
+  best: string;
+  others: string[];
+  similarity_pct: number;
+  pairs: SimilarPair[];
+}
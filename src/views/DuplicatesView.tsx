@@ -1,11 +1,11 @@
 import { Ic, catIcon } from "../Icons";
 import { fmtSize } from "../helpers";
-import type { FileMeta } from "../types";
+import type { DuplicateGroup } from "../types";
 
 interface Props {
-  groups:         FileMeta[][];
+  groups:         DuplicateGroup[];
   onOpen:         (path: string) => void;
-  onShowProps:    (hash: string) => void;
+  onShowProps:    (hash: string, path: string) => void;
   onDeleteToBin:  (hash: string, path: string) => void;
 }
 
@@ -16,7 +16,8 @@ export default function DuplicatesView({ groups, onOpen, onShowProps, onDeleteTo
         Duplicate Files — {groups.length} groups
         {groups.length > 0 && (
           <span className="panel-sub">
-            {groups.reduce((n, g) => n + g.length - 1, 0)} redundant copies
+            {groups.reduce((n, g) => n + g.files.length - 1, 0)} redundant copies
+            · {fmtSize(groups.reduce((n, g) => n + g.wasted_bytes, 0))} reclaimable
           </span>
         )}
       </div>
@@ -32,20 +33,22 @@ export default function DuplicatesView({ groups, onOpen, onShowProps, onDeleteTo
       {groups.map((group, i) => (
         <div key={i} className="dup-group">
           <div className="dup-hdr">
-            <span className="dup-hash">{group[0].hash.substring(0, 24)}…</span>
-            <span className="dup-info">{group.length} copies · {fmtSize(group[0].size)}</span>
+            <span className="dup-hash">{group.hash.substring(0, 24)}…</span>
+            <span className="dup-info">
+              {group.files.length} copies · {fmtSize(group.size)} · {fmtSize(group.wasted_bytes)} wasted
+            </span>
           </div>
-          {group.map((f, j) => (
+          {group.files.map((f, j) => (
             <div key={j} className="list-row">
               <span className="row-ico">{catIcon(f.category)}</span>
               <span className="row-path" onClick={() => onOpen(f.path)} title={f.path}>
                 {f.path}
               </span>
               <div className="row-acts">
-                <button className="btn-xs" onClick={() => onShowProps(f.hash)}>
+                <button className="btn-xs" onClick={() => onShowProps(group.hash, f.path)}>
                   <Ic.Info /> Info
                 </button>
-                <button className="btn-xs red" onClick={() => onDeleteToBin(f.hash, f.path)}>
+                <button className="btn-xs red" onClick={() => onDeleteToBin(group.hash, f.path)}>
                   <Ic.Trash /> Bin
                 </button>
               </div>
@@ -1,3 +1,4 @@
+import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { Ic, catIcon } from "../Icons";
 import { fmtSize, fmtTime } from "../helpers";
@@ -6,15 +7,30 @@ import type { DeletedEntry } from "../types";
 interface Props {
   deleted:        DeletedEntry[];
   onRefresh:      () => void;
+  onRestored:     (msg: string) => void;
 }
 
-export default function HistoryView({ deleted, onRefresh }: Props) {
+export default function HistoryView({ deleted, onRefresh, onRestored }: Props) {
+  const [keepCopies, setKeepCopies] = useState(false);
+
+  useEffect(() => { invoke<boolean>("get_blob_store_enabled").then(setKeepCopies).catch(() => {}); }, []);
+
   const handleClearAll = async () => {
     if (!confirm("Clear all deletion records? This does not restore the files.")) return;
     await invoke("clear_deleted_history");
     onRefresh();
   };
 
+  const toggleKeepCopies = async () => {
+    await invoke("set_blob_store_enabled", { enabled: !keepCopies });
+    setKeepCopies(!keepCopies);
+  };
+
+  const handleRestore = async (e: DeletedEntry) => {
+    try { onRestored(await invoke<string>("restore_deleted", { hash: e.hash, targetPath: e.path })); }
+    catch (err) { alert(`Restore failed: ${err}`); }
+  };
+
   return (
     <div className="full-panel">
       <div className="panel-title">
@@ -29,7 +45,11 @@ export default function HistoryView({ deleted, onRefresh }: Props) {
       <div className="history-note">
         Files sent to the <strong>Windows Recycle Bin</strong> appear here.
         Restore them from the Recycle Bin on your desktop.
-        Files marked <span className="perm-tag">Permanent</span> are gone forever.
+        Files marked <span className="perm-tag">Permanent</span> are gone forever
+        unless a copy was kept in the vault.
+        <label style={{ display: "block", marginTop: 6 }}>
+          <input type="checkbox" checked={keepCopies} onChange={toggleKeepCopies} /> Keep a copy of files before deleting or overwriting them
+        </label>
       </div>
 
       {deleted.length === 0 && (
@@ -54,8 +74,13 @@ export default function HistoryView({ deleted, onRefresh }: Props) {
             <span>{fmtTime(e.deleted_at)}</span>
             {e.snapshot_name === "permanent"
               ? <span className="perm-tag">Permanent</span>
-              : <span className="bin-tag">In Recycle Bin</span>
+              : e.snapshot_name === "overwritten"
+                ? <span className="perm-tag">Overwritten</span>
+                : <span className="bin-tag">In Recycle Bin</span>
             }
+            {e.blob_stored && (
+              <button className="btn-xs" onClick={() => handleRestore(e)}>Restore</button>
+            )}
           </div>
         </div>
       ))}
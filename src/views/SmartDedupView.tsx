@@ -2,26 +2,62 @@ import { useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { Ic, catIcon } from "../Icons";
 import { fmtSize } from "../helpers";
-import type { FileMeta } from "../types";
+import type { FileMeta, HashAlgorithm, HashMatch, HashThreshold, ImageTransform, SimilarGroup } from "../types";
 
-interface SimilarGroup {
+interface SimilarFile {
+  file: FileMeta;
+  matchedTo: FileMeta;
+  transform: ImageTransform;
+  hashes: HashMatch[];
+}
+
+interface ResolvedGroup {
   best: FileMeta;
-  others: FileMeta[];
+  others: SimilarFile[];
   similarity_pct: number;
 }
 
+const ALGORITHMS: { id: HashAlgorithm; label: string; hint: string }[] = [
+  { id: "phash", label: "pHash", hint: "DCT — re-saves, resizes, mild colour changes" },
+  { id: "ahash", label: "aHash", hint: "Average — fast, sensitive to brightness edits" },
+  { id: "dhash", label: "dHash", hint: "Gradient — exposure and colour grading" },
+  { id: "whash", label: "wHash", hint: "Wavelet — crops and local edits" },
+];
+
+const LABEL: Record<HashAlgorithm, string> = { phash: "pHash", ahash: "aHash", dhash: "dHash", whash: "wHash" };
+
+const TRANSFORM_LABEL: Record<ImageTransform, string> = {
+  identity:        "",
+  rotate90:        "rotated 90°",
+  rotate180:       "rotated 180°",
+  rotate270:       "rotated 270°",
+  flip_horizontal: "mirrored",
+  flip_vertical:   "flipped upside down",
+  transpose:       "rotated 90° and mirrored",
+  transverse:      "rotated 270° and mirrored",
+};
+
 interface Props {
   allFiles:      FileMeta[];
   onOpen:        (path: string) => void;
   onDeleteToBin: (hash: string, path: string) => void;
-  onShowProps:   (hash: string) => void;
+  onShowProps:   (hash: string, path: string) => void;
 }
 
 export default function SmartDedupView({ allFiles, onOpen, onDeleteToBin, onShowProps }: Props) {
-  const [groups, setGroups]     = useState<SimilarGroup[]>([]);
+  const [groups, setGroups]     = useState<ResolvedGroup[]>([]);
   const [scanning, setScanning] = useState(false);
   const [done, setDone]         = useState(false);
-  const [threshold, setThreshold] = useState(90);
+  // Per-algorithm similarity %, or null when the algorithm is off
+  const [similarity, setSimilarity] = useState<Record<HashAlgorithm, number | null>>({
+    phash: 90, ahash: null, dhash: null, whash: null,
+  });
+  const [requireAll, setRequireAll] = useState(true);
+  const [anyOrientation, setAnyOrientation] = useState(true);
+
+  const selected: HashThreshold[] = ALGORITHMS
+    .filter(a => similarity[a.id] !== null)
+    .map(a => ({ algorithm: a.id, threshold: 100 - similarity[a.id]! })); // similarity% → max hamming distance
 
   const imageFiles = allFiles.filter(f => f.category === "image");
 
@@ -29,13 +65,19 @@ export default function SmartDedupView({ allFiles, onOpen, onDeleteToBin, onShow
     setScanning(true);
     setDone(false);
     try {
-      const raw = await invoke<[string, string[], number][]>("find_similar_images", {
-        threshold: 100 - threshold, // convert similarity% to max hamming distance
+      const raw = await invoke<SimilarGroup[]>("find_similar_images", {
+        threshold: selected[0].threshold,
+        algorithms: selected,
+        requireAll,
+        anyOrientation,
       });
-      const parsed: SimilarGroup[] = raw.map(([bestHash, otherHashes, simPct]) => {
-        const best = allFiles.find(f => f.hash === bestHash)!;
-        const others = otherHashes.map(h => allFiles.find(f => f.hash === h)).filter(Boolean) as FileMeta[];
-        return { best, others, similarity_pct: simPct };
+      const byHash = new Map(allFiles.map(f => [f.hash, f]));
+      const parsed: ResolvedGroup[] = raw.map(g => {
+        const best = byHash.get(g.best)!;
+        const others = g.pairs
+          .map(p => ({ file: byHash.get(p.hash)!, matchedTo: byHash.get(p.matched_to)!, transform: p.transform, hashes: p.hashes }))
+          .filter(o => o.file && o.matchedTo);
+        return { best, others, similarity_pct: g.similarity_pct };
       }).filter(g => g.best && g.others.length > 0);
       setGroups(parsed);
       setDone(true);
@@ -58,23 +100,50 @@ export default function SmartDedupView({ allFiles, onOpen, onDeleteToBin, onShow
       <div className="smartdup-intro">
         <p>
           Finds images that look <strong>nearly identical</strong> even if they are different files —
-          different resolutions, slight crops, or re-saves. Uses perceptual hashing (DCT) in Rust.
+          different resolutions, slight crops, or re-saves. Uses perceptual hashing in Rust; combine
+          several hashes to catch more kinds of edits.
         </p>
         <p style={{ marginTop: 6, color: "var(--t3)", fontSize: 11 }}>
           {imageFiles.length} images indexed · Only images are compared
         </p>
+        {ALGORITHMS.map(a => {
+          const pct = similarity[a.id];
+          return (
+            <div key={a.id} className="threshold-row">
+              <label>
+                <input type="checkbox" checked={pct !== null}
+                  onChange={e => setSimilarity(s => ({ ...s, [a.id]: e.target.checked ? 90 : null }))} />
+                {" "}{a.label}{pct !== null && <>: <strong>{pct}%</strong></>}
+              </label>
+              {pct !== null && (
+                <input type="range" min={70} max={99} value={pct}
+                  onChange={e => setSimilarity(s => ({ ...s, [a.id]: Number(e.target.value) }))}
+                  className="threshold-slider" />
+              )}
+              <span className="threshold-hint">
+                {pct === null ? a.hint :
+                 pct >= 95 ? "Very strict — nearly pixel-perfect" :
+                 pct >= 85 ? "Balanced — catches most near-dupes" :
+                 "Loose — may include different images"}
+              </span>
+            </div>
+          );
+        })}
+        {selected.length > 1 && (
+          <div className="threshold-row">
+            <label>
+              <input type="checkbox" checked={requireAll} onChange={e => setRequireAll(e.target.checked)} />
+              {" "}Every selected hash must match <span className="threshold-hint">(otherwise any one is enough)</span>
+            </label>
+          </div>
+        )}
         <div className="threshold-row">
-          <label>Similarity threshold: <strong>{threshold}%</strong></label>
-          <input type="range" min={70} max={99} value={threshold}
-            onChange={e => setThreshold(Number(e.target.value))}
-            className="threshold-slider" />
-          <span className="threshold-hint">
-            {threshold >= 95 ? "Very strict — nearly pixel-perfect" :
-             threshold >= 85 ? "Balanced — catches most near-dupes" :
-             "Loose — may include different images"}
-          </span>
+          <label>
+            <input type="checkbox" checked={anyOrientation} onChange={e => setAnyOrientation(e.target.checked)} />
+            {" "}Match rotated and mirrored copies
+          </label>
         </div>
-        <button className="btn-primary" onClick={runScan} disabled={scanning || imageFiles.length === 0}>
+        <button className="btn-primary" onClick={runScan} disabled={scanning || imageFiles.length === 0 || selected.length === 0}>
           {scanning ? "Scanning…" : `Scan ${imageFiles.length} Images`}
         </button>
       </div>
@@ -89,7 +158,7 @@ export default function SmartDedupView({ allFiles, onOpen, onDeleteToBin, onShow
       {done && groups.length === 0 && (
         <div className="empty-state">
           <div className="eico"><Ic.Check /></div>
-          <p>No similar images found at {threshold}% threshold</p>
+          <p>No similar images found at these thresholds</p>
           <small>Try lowering the threshold to find more matches</small>
         </div>
       )}
@@ -117,24 +186,29 @@ export default function SmartDedupView({ allFiles, onOpen, onDeleteToBin, onShow
             </div>
             <div className="row-acts">
               <button className="btn-xs" onClick={() => onOpen(group.best.path)}>Open</button>
-              <button className="btn-xs" onClick={() => onShowProps(group.best.hash)}>
+              <button className="btn-xs" onClick={() => onShowProps(group.best.hash, group.best.path)}>
                 <Ic.Info />
               </button>
             </div>
           </div>
 
           {/* Similar files */}
-          {group.others.map((f, j) => (
+          {group.others.map(({ file: f, matchedTo, transform, hashes }, j) => (
             <div key={j} className="list-row">
               <span className="row-ico" style={{ color: "#f87171" }}>{catIcon("image")}</span>
               <div className="row-info">
                 <div className="row-name">{f.name}</div>
                 <div className="row-sub">{f.path}</div>
                 <div className="row-sub">{fmtSize(f.size)}</div>
+                <div className="row-sub">
+                  {matchedTo.hash !== group.best.hash && <>via {matchedTo.name} · </>}
+                  {transform !== "identity" && <>{TRANSFORM_LABEL[transform]} · </>}
+                  {hashes.map(h => `${LABEL[h.algorithm]} ${h.distance} ${h.matched ? "✓" : "✗"}`).join(" · ")}
+                </div>
               </div>
               <div className="row-acts">
                 <button className="btn-xs" onClick={() => onOpen(f.path)}>Open</button>
-                <button className="btn-xs" onClick={() => onShowProps(f.hash)}>
+                <button className="btn-xs" onClick={() => onShowProps(f.hash, f.path)}>
                   <Ic.Info />
                 </button>
                 <button className="btn-xs red" onClick={() => onDeleteToBin(f.hash, f.path)}>
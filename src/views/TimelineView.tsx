@@ -1,17 +1,28 @@
 import { useState, useMemo, useRef, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
 import { Ic, catIcon } from "../Icons";
 import { fmtSize, fmtTime, groupByDate, catColor } from "../helpers";
-import type { FileMeta } from "../types";
+import type { DiffEntry, FileMeta, SnapshotInfo } from "../types";
 
 interface Props {
   allFiles:    FileMeta[];
+  snapshots:   SnapshotInfo[];
   onOpen:      (path: string) => void;
-  onShowProps: (hash: string) => void;
+  onShowProps: (hash: string, path: string) => void;
   onCtx:       (e: React.MouseEvent, item: any) => void;
 }
 
-export default function TimelineView({ allFiles, onOpen, onShowProps, onCtx }: Props) {
+// Diffs are cached per snapshot ("{ts}::{name}") and per snapshot-vs-disk ("…::disk"); a string is an error
+type Changes = Record<string, DiffEntry[] | string>;
+
+const snapKey = (s: SnapshotInfo) => `${s.timestamp}::${s.name}`;
+
+export default function TimelineView({ allFiles, snapshots, onOpen, onShowProps, onCtx }: Props) {
   const [dateMode, setDateMode] = useState<"modified" | "indexed">("modified");
+  const [showChanges, setShowChanges] = useState(false);
+  const [changes, setChanges] = useState<Changes>({});
+  const [loading, setLoading] = useState<Set<string>>(new Set());
+  const [expandedSnaps, setExpandedSnaps] = useState<Set<string>>(new Set());
   const [filterCat, setFilterCat] = useState("all");
   const [expandedDates, setExpandedDates] = useState<Set<string>>(new Set());
   const containerRef = useRef<HTMLDivElement>(null);
@@ -39,24 +50,87 @@ export default function TimelineView({ allFiles, onOpen, onShowProps, onCtx }: P
 
   const totalFiles = groups.reduce((n, g) => n + g.files.length, 0);
 
+  // Each snapshot against the previous one of the same folder, newest first
+  const history = useMemo(() => {
+    const sorted = [...snapshots].sort((a, b) => b.timestamp - a.timestamp);
+    return sorted.map(snap => ({
+      snap,
+      previous: sorted.find(p => p.folder_path === snap.folder_path && p.timestamp < snap.timestamp) ?? null,
+    }));
+  }, [snapshots]);
+
+  const load = async (key: string, fetch: () => Promise<DiffEntry[]>, refresh = false) => {
+    if ((!refresh && changes[key] !== undefined) || loading.has(key)) return;
+    setLoading(prev => new Set(prev).add(key));
+    let result: DiffEntry[] | string;
+    try { result = await fetch(); } catch (e) { result = String(e); }
+    setChanges(prev => ({ ...prev, [key]: result }));
+    setLoading(prev => { const next = new Set(prev); next.delete(key); return next; });
+  };
+
+  const toggleSnapshot = (snap: SnapshotInfo, previous: SnapshotInfo | null) => {
+    const key = snapKey(snap);
+    setExpandedSnaps(prev => {
+      const next = new Set(prev);
+      if (next.has(key)) next.delete(key);
+      else next.add(key);
+      return next;
+    });
+    if (previous) {
+      load(key, () => invoke<DiffEntry[]>("diff_snapshots", {
+        oldName: previous.name, oldTimestamp: previous.timestamp,
+        newName: snap.name, newTimestamp: snap.timestamp,
+      }));
+    }
+  };
+
+  const compareWithDisk = (snap: SnapshotInfo) => {
+    // The disk keeps changing, so every click compares afresh
+    load(`${snapKey(snap)}::disk`, () => invoke<DiffEntry[]>("diff_snapshot_with_disk", {
+      snapshotName: snap.name, timestamp: snap.timestamp, jobId: `diff-${Date.now()}`,
+    }), true);
+  };
+
+  const renderChanges = (key: string) => {
+    const result = changes[key];
+    if (loading.has(key)) return <div className="tl-note">Comparing…</div>;
+    if (result === undefined) return null;
+    if (typeof result === "string") return <div className="tl-note red">{result}</div>;
+    if (result.length === 0) return <div className="tl-note">No changes</div>;
+    return result.map(c => (
+      <div key={`${c.change}:${c.path}`} className="tl-change" onDoubleClick={() => onOpen(c.path)}>
+        <span className={`tl-change-kind ${c.change}`}>{c.change}</span>
+        <span className="tl-change-path" title={c.path}>
+          {c.old_path ? `${c.old_path} → ${c.path}` : c.path}
+        </span>
+        <span className="tl-card-meta">{fmtSize(c.size)}</span>
+      </div>
+    ));
+  };
+
   return (
     <div className="full-panel timeline-panel">
       {/* Controls */}
       <div className="timeline-controls">
         <div className="tl-toggle">
           <button
-            className={`tl-btn ${dateMode === "modified" ? "active" : ""}`}
-            onClick={() => setDateMode("modified")}>
+            className={`tl-btn ${!showChanges && dateMode === "modified" ? "active" : ""}`}
+            onClick={() => { setDateMode("modified"); setShowChanges(false); }}>
             File Modified Date
           </button>
           <button
-            className={`tl-btn ${dateMode === "indexed" ? "active" : ""}`}
-            onClick={() => setDateMode("indexed")}>
+            className={`tl-btn ${!showChanges && dateMode === "indexed" ? "active" : ""}`}
+            onClick={() => { setDateMode("indexed"); setShowChanges(false); }}>
             Date Indexed
           </button>
+          <button
+            className={`tl-btn ${showChanges ? "active" : ""}`}
+            onClick={() => setShowChanges(true)}>
+            Snapshot Changes
+          </button>
         </div>
 
-        <div className="tl-cats">
+        {!showChanges && <div className="tl-cats">
           {["all","image","video","document","audio","archive"].map(cat => (
             <button key={cat}
               className={`tl-cat ${filterCat === cat ? "active" : ""}`}
@@ -64,14 +138,62 @@ export default function TimelineView({ allFiles, onOpen, onShowProps, onCtx }: P
               {cat === "all" ? "All" : cat.charAt(0).toUpperCase() + cat.slice(1)}
             </button>
           ))}
-        </div>
+        </div>}
 
         <div className="tl-summary">
-          {totalFiles} files across {groups.length} dates
+          {showChanges
+            ? `${snapshots.length} snapshots`
+            : `${totalFiles} files across ${groups.length} dates`}
         </div>
       </div>
 
-      {groups.length === 0 && (
+      {showChanges && (
+        <div className="timeline-scroll">
+          {history.length === 0 && (
+            <div className="empty-state">
+              <div className="eico"><Ic.Timeline /></div>
+              <p>No snapshots yet</p>
+              <small>Index a folder to take the first one</small>
+            </div>
+          )}
+          {history.map(({ snap, previous }) => {
+            const key = snapKey(snap);
+            const expanded = expandedSnaps.has(key);
+            const diff = changes[key];
+            return (
+              <div key={key} className="tl-group">
+                <div className="tl-date-hdr" onClick={() => toggleSnapshot(snap, previous)}>
+                  <div className="tl-dot" />
+                  <div className="tl-date-text">
+                    <span className="tl-date-label">{snap.name} · {fmtTime(snap.timestamp)}</span>
+                    <span className="tl-date-count">
+                      {snap.folder_path} · {snap.file_count} files
+                      {Array.isArray(diff) ? ` · ${diff.length} changes since ${previous?.name}` : ""}
+                    </span>
+                  </div>
+                  <button className="btn-xs" onClick={e => { e.stopPropagation(); if (!expanded) toggleSnapshot(snap, previous); compareWithDisk(snap); }}>
+                    Compare with disk
+                  </button>
+                  <span className="tl-chevron">{expanded ? "▾" : "▸"}</span>
+                </div>
+                {expanded && (
+                  <div className="tl-changes">
+                    {previous ? renderChanges(key) : <div className="tl-note">First snapshot of this folder</div>}
+                    {(changes[`${key}::disk`] !== undefined || loading.has(`${key}::disk`)) && (
+                      <>
+                        <div className="tl-note">Since this snapshot, on disk now:</div>
+                        {renderChanges(`${key}::disk`)}
+                      </>
+                    )}
+                  </div>
+                )}
+              </div>
+            );
+          })}
+        </div>
+      )}
+
+      {!showChanges && groups.length === 0 && (
         <div className="empty-state">
           <div className="eico"><Ic.Timeline /></div>
           <p>No files to show in timeline</p>
@@ -80,7 +202,7 @@ export default function TimelineView({ allFiles, onOpen, onShowProps, onCtx }: P
       )}
 
       {/* Timeline */}
-      <div className="timeline-scroll" ref={containerRef}>
+      {!showChanges && <div className="timeline-scroll" ref={containerRef}>
         {groups.map(group => {
           const expanded = expandedDates.has(group.date);
           return (
@@ -99,9 +221,9 @@ export default function TimelineView({ allFiles, onOpen, onShowProps, onCtx }: P
               {expanded && (
                 <div className="tl-files">
                   {group.files.map(f => (
-                    <div key={f.hash} className="tl-card"
+                    <div key={f.path} className="tl-card"
                       style={{ "--cc": catColor[f.category] || "#94a3b8" } as React.CSSProperties}
-                      onClick={() => onShowProps(f.hash)}
+                      onClick={() => onShowProps(f.hash, f.path)}
                       onDoubleClick={() => onOpen(f.path)}
                       onContextMenu={e => onCtx(e, { ...f, isFolder: false })}>
                       <div className={`tl-card-ico ${f.category || "other"}`}>
@@ -116,7 +238,7 @@ export default function TimelineView({ allFiles, onOpen, onShowProps, onCtx }: P
             </div>
           );
         })}
-      </div>
+      </div>}
     </div>
   );
 }
\ No newline at end of file